@@ -3,50 +3,957 @@ use leptos::{web_sys::{Node,Element},prelude::{Mountable, Owner, Render}, IntoVi
 #[cfg(any(feature="csr",feature="hydrate"))]
 use leptos::wasm_bindgen::JsCast;
 
+/// Attribute set on the top-level elements of every replacement view inserted by this crate.
+/// It is never removed again; so if a replacement reinserts original markup at its top level (e.g. via
+/// [`wrap_original`](crate::wrap_original)), that markup keeps the attribute.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) const HYDRATED_MARKER: &str = "data-leptos-hydrated";
+
+// whether `e` is (part of) a replacement inserted by this crate below `top`
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn is_hydrated(top:&Node,e:&Element) -> bool {
+  let Ok(Some(m)) = e.closest(&format!("[{HYDRATED_MARKER}]")) else { return false };
+  *m != *top && top.contains(Some(&m))
+}
+
+/// Errors that can occur while hydrating a DOM node.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum HydrateError {
+  /// An element to be replaced has no parent element to insert the replacement into.
+  DetachedNode,
+  /// The replacement view could not be mounted into the DOM; the original node is left in place.
+  MountFailed,
+  /// No element matches the given selector.
+  NotFound(String),
+  /// The host element has no (open) shadow root.
+  NoShadowRoot,
+  /// A DOM operation threw an exception.
+  Dom(String)
+}
+
+impl std::fmt::Display for HydrateError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::DetachedNode => f.write_str("node to replace has no parent element"),
+      Self::MountFailed => f.write_str("failed to mount replacement view"),
+      Self::NotFound(s) => write!(f, "no element matches selector {s}"),
+      Self::NoShadowRoot => f.write_str("element has no open shadow root"),
+      Self::Dom(s) => write!(f, "DOM error: {s}")
+    }
+  }
+}
+
+impl std::error::Error for HydrateError {}
+
+impl From<leptos::wasm_bindgen::JsValue> for HydrateError {
+  fn from(value: leptos::wasm_bindgen::JsValue) -> Self {
+    Self::Dom(value.as_string().unwrap_or_else(|| format!("{value:?}")))
+  }
+}
+
+type Predicate = std::sync::Arc<dyn Fn(&leptos::web_sys::Element) -> bool + Send + Sync>;
+type Hook = std::sync::Arc<dyn Fn(&leptos::web_sys::Element) + Send + Sync>;
+type MountHook = std::sync::Arc<dyn Fn(&leptos::web_sys::Element,&leptos::web_sys::Node) + Send + Sync>;
+type TextReplacer = std::sync::Arc<dyn Fn(&leptos::web_sys::Text) -> Option<crate::RuleView> + Send + Sync>;
+type CommentReplacer = std::sync::Arc<dyn Fn(&leptos::web_sys::Comment) -> Option<crate::RuleView> + Send + Sync>;
+#[cfg(any(feature="csr",feature="hydrate"))]
+type GroupReplacer = std::sync::Arc<dyn Fn(crate::OriginalNode) -> crate::RuleView + Send + Sync>;
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+#[derive(Clone)]
+struct RangeRule {
+  start:Predicate,
+  end:Predicate,
+  view:GroupReplacer
+}
+
+/// The root DOM nodes of mounted replacements, as collected by [`HydrateOpts::collect_roots`]. Clones share
+/// the same list.
+#[derive(Clone,Default)]
+pub struct MountedRoots(std::sync::Arc<std::sync::Mutex<Vec<send_wrapper::SendWrapper<leptos::web_sys::Node>>>>);
+
+impl MountedRoots {
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Removes and returns all nodes collected so far.
+  pub fn take(&self) -> Vec<leptos::web_sys::Node> {
+    std::mem::take(&mut *self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner))
+      .into_iter().map(send_wrapper::SendWrapper::take).collect()
+  }
+
+  /// The number of nodes collected so far.
+  pub fn len(&self) -> usize {
+    self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner).len()
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  fn push(&self,node:Node) {
+    self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner).push(send_wrapper::SendWrapper::new(node));
+  }
+}
+
+/// Options for [`hydrate_node_with`], restricting which parts of the DOM are traversed.
+#[derive(Clone,Default)]
+pub struct HydrateOpts {
+  max_depth:Option<usize>,
+  skip:Option<Predicate>,
+  replace_text:Option<TextReplacer>,
+  replace_comment:Option<CommentReplacer>,
+  force:bool,
+  on_before_remove:Option<Hook>,
+  on_mounted:Option<MountHook>,
+  on_each:Option<Hook>,
+  roots:Option<MountedRoots>,
+  descend_if:Option<Predicate>,
+  // stored negated, so that the default includes the root
+  exclude_root:bool,
+  continue_on_error:bool,
+  remove:Option<Predicate>,
+  traverse_templates:bool,
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  range:Option<RangeRule>,
+  skip_whitespace_text:bool,
+  preserve_focus:bool,
+  focus_selector:Option<String>,
+  upgrade_noscript:bool,
+  // register cleanups via `on_cleanup_local` rather than in a `SendWrapper` (see `local`)
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  local:bool
+}
+
+impl HydrateOpts {
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Only visits nodes at most `depth` levels below the root of the traversal (which has depth `0`).
+  #[inline]
+  pub fn max_depth(mut self,depth:usize) -> Self {
+    self.max_depth = Some(depth);
+    self
+  }
+
+  /// Leaves elements for which `f` returns `true` (and their descendants) untouched.
+  #[inline]
+  pub fn skip(mut self,f:impl Fn(&leptos::web_sys::Element) -> bool + Send + Sync + 'static) -> Self {
+    self.skip = Some(std::sync::Arc::new(f));
+    self
+  }
+
+  /// Additionally calls `f` on every text node; if it returns `Some`, the text node is replaced by the
+  /// resulting view (e.g. to linkify URLs). Text nodes for which `f` returns `None` are left untouched.
+  pub fn replace_text<
+    V:leptos::prelude::IntoView+'static,
+    R:FnOnce() -> V + 'static
+  >(mut self,f:impl Fn(&leptos::web_sys::Text) -> Option<R> + Send + Sync + 'static) -> Self {
+    use leptos::prelude::IntoAny;
+    self.replace_text = Some(std::sync::Arc::new(move |t| f(t).map(|r| Box::new(move || r().into_any()) as crate::RuleView)));
+    self
+  }
+
+  /// Don't call the [`replace_text`](HydrateOpts::replace_text) function on text nodes consisting only of
+  /// whitespace (e.g. the indentation of server-rendered HTML). The DOM is left as is.
+  #[inline]
+  pub fn skip_whitespace_text(mut self,skip:bool) -> Self {
+    self.skip_whitespace_text = skip;
+    self
+  }
+
+  /// Additionally calls `f` on every comment node; if it returns `Some`, the comment is replaced by the
+  /// resulting view - e.g. for content using comments as markers (`<!-- widget:chart -->`) rather than attributes.
+  pub fn replace_comment<
+    V:leptos::prelude::IntoView+'static,
+    R:FnOnce() -> V + 'static
+  >(mut self,f:impl Fn(&leptos::web_sys::Comment) -> Option<R> + Send + Sync + 'static) -> Self {
+    use leptos::prelude::IntoAny;
+    self.replace_comment = Some(std::sync::Arc::new(move |c| f(c).map(|r| Box::new(move || r().into_any()) as crate::RuleView)));
+    self
+  }
+
+  /// Replaces ranges of sibling nodes by a single view: When an element matching `start` is encountered, it and
+  /// all of its following siblings up to and including the next one matching `end` (or up to the last sibling,
+  /// if there is none) are removed, and `view` is called with an [`OriginalNode`](crate::OriginalNode) containing
+  /// the nodes *between* the two markers; e.g. to turn
+  /// `<span data-group-start></span>...<span data-group-end></span>` into a single `<Accordion>`:
+  /// ```ignore
+  /// HydrateOpts::new().replace_range(
+  ///   |e| e.has_attribute("data-group-start"),
+  ///   |e| e.has_attribute("data-group-end"),
+  ///   |orig| move || view!(<Accordion><DomChildren orig/></Accordion>)
+  /// )
+  /// ```
+  /// The traversal continues after the consumed range; ranges take precedence over `replace`.
+  pub fn replace_range<
+    V:leptos::prelude::IntoView+'static,
+    R:FnOnce() -> V + 'static
+  >(
+    self,
+    start:impl Fn(&leptos::web_sys::Element) -> bool + Send + Sync + 'static,
+    end:impl Fn(&leptos::web_sys::Element) -> bool + Send + Sync + 'static,
+    view:impl Fn(crate::OriginalNode) -> R + Send + Sync + 'static
+  ) -> Self {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      use leptos::prelude::IntoAny;
+      Self { range:Some(RangeRule {
+        start:std::sync::Arc::new(start),
+        end:std::sync::Arc::new(end),
+        view:std::sync::Arc::new(move |orig| { let r = view(orig); Box::new(move || r().into_any()) as crate::RuleView })
+      }), ..self }
+    }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { let _ = (start,end,view); self }
+  }
+
+  /// Elements that have already been replaced by this crate (and their descendants) are skipped,
+  /// to avoid hydrating the same subtree twice. Setting `force` disables this check. The root of the
+  /// traversal itself is never skipped for that reason.
+  #[inline]
+  pub fn force(mut self,force:bool) -> Self {
+    self.force = force;
+    self
+  }
+
+  /// Calls `f` on every replaced element right before it is removed from the DOM, e.g. to snapshot
+  /// its scroll position or focus. `f` runs under the reactive owner of the replacement, so context
+  /// provided in `f` is available to the replacement view.
+  #[inline]
+  pub fn on_before_remove(mut self,f:impl Fn(&leptos::web_sys::Element) + Send + Sync + 'static) -> Self {
+    self.on_before_remove = Some(std::sync::Arc::new(f));
+    self
+  }
+
+  /// Calls `f` after each replacement has been mounted, with the original (now removed) element and the
+  /// first DOM node of the replacement, e.g. to initialize a JS library on the new nodes.
+  #[inline]
+  pub fn on_mounted(mut self,f:impl Fn(&leptos::web_sys::Element,&leptos::web_sys::Node) + Send + Sync + 'static) -> Self {
+    self.on_mounted = Some(std::sync::Arc::new(f));
+    self
+  }
+
+  /// Pushes the first DOM node of every replacement onto `roots` once it is mounted, in document order; e.g. to
+  /// focus, scroll to or measure them later:
+  /// ```ignore
+  /// let roots = MountedRoots::new();
+  /// hydrate_node_with(node, &replace, &HydrateOpts::new().collect_roots(&roots));
+  /// for n in roots.take() { /* ... */ }
+  /// ```
+  #[inline]
+  pub fn collect_roots(mut self,roots:&MountedRoots) -> Self {
+    self.roots = Some(roots.clone());
+    self
+  }
+
+  /// Calls `f` on every element visited by the traversal - whether it is subsequently replaced or not - right
+  /// before it is passed to `replace`; e.g. to add a CSS class or collect headings for a table of contents.
+  /// Elements are visited in document order (DFS); [skipped](HydrateOpts::skip) elements and descendants of
+  /// replaced elements are not visited.
+  #[inline]
+  pub fn on_each(mut self,f:impl Fn(&leptos::web_sys::Element) + Send + Sync + 'static) -> Self {
+    self.on_each = Some(std::sync::Arc::new(f));
+    self
+  }
+
+  /// If a replaced element (or one of its descendants) has focus, moves the focus to the corresponding node of
+  /// the replacement once it is mounted: the element with the same `id` as the previously focused one, if any,
+  /// and otherwise the first one matching the [`focus_selector`](HydrateOpts::focus_selector). This keeps
+  /// keyboard navigation from jumping to the top of the page mid-hydration.
+  ///
+  /// Note that ARIA relationships (`aria-activedescendant`, `aria-controls` etc.) refer to ids, so they are kept
+  /// intact as long as the replacement reuses the ids of the original (e.g. via [`with_inherited_attrs`](crate::with_inherited_attrs)).
+  #[inline]
+  pub fn preserve_focus(mut self,preserve:bool) -> Self {
+    self.preserve_focus = preserve;
+    self
+  }
+
+  /// The CSS selector of the node to move the focus to, if the previously focused element has no `id` or the
+  /// replacement contains no element with the same one (see [`preserve_focus`](HydrateOpts::preserve_focus),
+  /// which this implies).
+  #[inline]
+  pub fn focus_selector(mut self,selector:impl Into<String>) -> Self {
+    self.preserve_focus = true;
+    self.focus_selector = Some(selector.into());
+    self
+  }
+
+  /// Only descends into (the children of) elements for which `f` returns `true`. Unlike [`skip`](HydrateOpts::skip),
+  /// the elements themselves are still passed to the `replace` function.
+  #[inline]
+  pub fn descend_if(mut self,f:impl Fn(&leptos::web_sys::Element) -> bool + Send + Sync + 'static) -> Self {
+    self.descend_if = Some(std::sync::Arc::new(f));
+    self
+  }
+
+  /// Whether the node passed to e.g. [`hydrate_node_with`] is itself eligible for replacement (default: `true`).
+  /// If `false`, only its descendants are passed to `replace`; this is what [`DomCont`](crate::DomCont) does
+  /// with `skip_head=true`. [`DomChildrenCont`](crate::DomChildrenCont) and friends never replace the
+  /// original element itself, only its children (and their descendants).
+  #[inline]
+  pub fn include_root(mut self,include:bool) -> Self {
+    self.exclude_root = !include;
+    self
+  }
+
+  /// Removes elements for which `f` returns `true` (e.g. `<script>`s or ad placeholders) from the DOM, without
+  /// inserting anything in their place or calling `replace` on them; unlike a replacement by an empty view,
+  /// this leaves no reactive owner or view state behind. Removed elements count as replaced.
+  #[inline]
+  pub fn remove(mut self,f:impl Fn(&leptos::web_sys::Element) -> bool + Send + Sync + 'static) -> Self {
+    self.remove = Some(std::sync::Arc::new(f));
+    self
+  }
+
+  /// Replaces every `<noscript>` element (that is not itself replaced) by its content, parsed as HTML, and
+  /// traverses the result like any other content. With scripting enabled, browsers don't parse the content of
+  /// `<noscript>`s, but expose it as text; so this allows for markup that search engines and non-JS clients see
+  /// as is, but that is only inserted (and hydrated) when the script runs.
+  #[inline]
+  pub fn upgrade_noscript(mut self,upgrade:bool) -> Self {
+    self.upgrade_noscript = upgrade;
+    self
+  }
+
+  /// Also traverses the contents of `<template>` elements (which live in a separate, inert document fragment,
+  /// and are hence not children of the template), e.g. for pages that stash markup in templates and clone it later.
+  #[inline]
+  pub fn traverse_templates(mut self,traverse:bool) -> Self {
+    self.traverse_templates = traverse;
+    self
+  }
+
+  /// If a replacement fails (e.g. because its view could not be mounted), log the error to the console and
+  /// continue with the remaining nodes, instead of aborting the whole pass. Useful for scripts embedded in
+  /// unpredictable pages, where partial success is better than none.
+  ///
+  /// Note that panics (e.g. in `replace` itself) can not be recovered from, since wasm builds abort on panic.
+  #[inline]
+  pub fn continue_on_error(mut self,cont:bool) -> Self {
+    self.continue_on_error = cont;
+    self
+  }
+
+  // the outcome of a replacement; with `continue_on_error`, errors are logged and the node counts as not replaced
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  fn recover(&self,res:Result<(),HydrateError>,node:&Node) -> Result<bool,HydrateError> {
+    match res {
+      Ok(()) => Ok(true),
+      Err(e) if self.continue_on_error => {
+        leptos::logging::error!("Error replacing {}: {e}",node.node_name());
+        Ok(false)
+      }
+      Err(e) => Err(e)
+    }
+  }
+
+  #[cfg(feature="csr")]
+  pub(crate) fn local(mut self) -> Self {
+    self.local = true;
+    self
+  }
+
+  // removes `node` from the DOM when the current owner is cleaned up (see `crate::cleanup`)
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  fn cleanup(&self,node:Node) {
+    if self.local { crate::cleanup_local(node) } else { crate::cleanup(node) }
+  }
+
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  fn skips(&self,e:&Element) -> bool {
+    (!self.force && e.has_attribute(HYDRATED_MARKER)) || self.skip.as_ref().is_some_and(|f| f(e))
+  }
+
+  // whether to descend into the children of a node at `depth`
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  fn descends(&self,depth:usize) -> bool {
+    self.max_depth.is_none_or(|m| depth < m)
+  }
+}
+
 /// Iterates over the node and its children (DFS) and replaces elements via the given function.
+/// Replacements inserted by a previous pass are skipped (see [`HydrateOpts::force`]).
+/// Returns the number of replaced elements.
+///
+/// The replacement views (i.e. the `R`s returned by `replace`) are evaluated under their own reactive owner,
+/// so context provided in them is scoped to the respective replacement (see [`with_context`](crate::with_context)).
+/// 
+/// Panics if a replacement fails; see [`try_hydrate_node`] for a non-panicking version.
 #[cfg(any(feature="csr",feature="hydrate"))]
 pub fn hydrate_node<
   V:IntoView+'static,
   R:FnOnce() -> V,
   F:Fn(&Element) -> Option<R>+'static
->(node:Node,replace:&F) {
-  // Check node returns a new index if it replaced the node, otherwise None.
-  if check_node(&node,&node,replace).0 {return}
+>(node:Node,replace:&F) -> usize {
+  try_hydrate_node(node, replace).expect("Error hydrating node")
+}
+
+/// Like [`hydrate_node`], but returns an error instead of panicking, and the number of replaced elements
+/// otherwise.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn try_hydrate_node<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,replace:&F) -> Result<usize,HydrateError> {
+  try_hydrate_node_mut(node, &mut |e:&Element| replace(e))
+}
+
+/// Like [`hydrate_node`], but accepts a stateful (`FnMut`) `replace` function.
+/// `replace` is called sequentially on the elements in DFS order, and never reentrantly.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_node_mut<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:FnMut(&Element) -> Option<R>
+>(node:Node,replace:&mut F) -> usize {
+  try_hydrate_node_mut(node, replace).expect("Error hydrating node")
+}
+
+/// Like [`try_hydrate_node`], but accepts a stateful (`FnMut`) `replace` function.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn try_hydrate_node_mut<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:FnMut(&Element) -> Option<R>
+>(node:Node,replace:&mut F) -> Result<usize,HydrateError> {
+  try_hydrate_node_opts(node, &mut |e:&Element,_:&[u32]| replace(e), &HydrateOpts::default())
+}
+
+/// Like [`hydrate_node`], but `replace` additionally gets the `Document` owning each element and the element's
+/// namespace URI (`None` for elements without one), e.g. to build nodes imperatively via `create_element_ns`
+/// against the right document - which is not the global `document` for content of iframes or (the inert
+/// documents of) `<template>`s:
+/// ```ignore
+/// hydrate_node_in_document(node, &|e:&Element, doc:&Document, ns:Option<&str>| e.has_attribute("data-icon").then(|| {
+///   let path = doc.create_element_ns(ns, "path").unwrap();
+///   move || view!(<Icon path/>)
+/// }));
+/// ```
+/// Elements without an owning document (which can not occur for elements in a DOM tree) are skipped.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_node_in_document<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element,&leptos::web_sys::Document,Option<&str>) -> Option<R>
+>(node:Node,replace:&F) -> usize {
+  try_hydrate_node_in_document(node, replace).expect("Error hydrating node")
+}
+
+/// Like [`hydrate_node_in_document`], but returns an error instead of panicking (see [`try_hydrate_node`]).
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn try_hydrate_node_in_document<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element,&leptos::web_sys::Document,Option<&str>) -> Option<R>
+>(node:Node,replace:&F) -> Result<usize,HydrateError> {
+  try_hydrate_node_mut(node, &mut |e:&Element| {
+    let doc = e.owner_document()?;
+    replace(e, &doc, e.namespace_uri().as_deref())
+  })
+}
+
+/// Like [`hydrate_node`], but only traverses the parts of the DOM allowed by `opts`.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_node_with<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,replace:&F,opts:&HydrateOpts) -> usize {
+  try_hydrate_node_with(node, replace, opts).expect("Error hydrating node")
+}
+
+/// Like [`hydrate_node_with`], but returns an error instead of panicking (see [`try_hydrate_node`]).
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn try_hydrate_node_with<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,replace:&F,opts:&HydrateOpts) -> Result<usize,HydrateError> {
+  try_hydrate_node_opts(node, &mut |e:&Element,_:&[u32]| replace(e), opts)
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn try_hydrate_node_opts<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:FnMut(&Element,&[u32]) -> Option<R>
+>(node:Node,replace:&mut F,opts:&HydrateOpts) -> Result<usize,HydrateError> {
+  if !opts.exclude_root {
+    // the marker is only checked for descendants: the root may itself be (the wrapper of) a replacement
+    // that hydrates its own content, e.g. a `DomStringCont` whose `on_load` runs after it has been marked
+    if node.dyn_ref::<Element>().is_some_and(|e| opts.skip.as_ref().is_some_and(|f| f(e))) {return Ok(0)}
+    if replace_root(&node,&[],replace,opts)? {return Ok(1)}
+  }
+  opts.cleanup(node.clone());
+  if !opts.descends(0) {return Ok(0)}
+  try_hydrate_children_path(node, &mut Vec::new(), replace, opts)
+}
+
+/// Like [`try_hydrate_node`], but yields to the browser (via `requestIdleCallback`, or `setTimeout`
+/// where that is unavailable) after every `nodes_per_slice` visited nodes, to avoid blocking the
+/// main thread on large subtrees. Traversal order and replacement semantics are the same as for
+/// [`hydrate_node`]; the returned future resolves once the whole subtree has been processed.
+/// 
+/// See [`hydrate_node_idle_cancellable`] for a version that can be aborted.
+#[cfg(any(feature="csr",feature="hydrate"))]
+#[inline]
+pub async fn hydrate_node_idle<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,replace:&F,nodes_per_slice:usize) -> Result<usize,HydrateError> {
+  hydrate_node_idle_cancellable(node, replace, nodes_per_slice, &CancelHandle::new()).await
+}
+
+/// Like [`hydrate_node_idle`], but stops once [`cancel`](CancelHandle::cancel) is called on (a clone of)
+/// `cancel` - e.g. when the user navigates away before hydration has finished. Elements that have
+/// already been replaced stay; the returned future then resolves (immediately) with their number.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub async fn hydrate_node_idle_cancellable<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,replace:&F,nodes_per_slice:usize,cancel:&CancelHandle) -> Result<usize,HydrateError> {
+  if cancel.is_cancelled() {return Ok(0)}
+  let mut replace = |e:&Element,_:&[u32]| replace(e);
+  // the reactive owner is not preserved across `.await`s
+  let owner = Owner::current();
+  let opts = HydrateOpts::default();
+  if with_owner(&owner,|| replace_root(&node,&[],&mut replace,&opts))? {return Ok(1)}
+  with_owner(&owner,|| crate::cleanup(node.clone()));
+  let mut count = 0;
+  let Some(mut current) = node.first_child() else { return Ok(0) };
+  let mut path = vec![0];
+  loop {
+    let mut budget = nodes_per_slice.max(1);
+    let next = with_owner(&owner,|| loop {
+      let (replaced,next) = check_node(&current, &node, &mut path, &mut replace, &opts)?;
+      count += replaced;
+      budget -= 1;
+      match next {
+        Some(n) if budget > 0 => current = n,
+        n => return Ok::<_,HydrateError>(n)
+      }
+    })?;
+    let Some(next) = next else { return Ok(count) };
+    current = next;
+    yield_to_browser(cancel,true).await;
+    if cancel.is_cancelled() {
+      trace!("idle hydration cancelled after {count} replacements");
+      return Ok(count)
+    }
+  }
+}
+
+/// A token for aborting [`hydrate_node_idle_cancellable`]. Clones share the same state.
+#[cfg(any(feature="csr",feature="hydrate"))]
+#[derive(Clone,Default)]
+pub struct CancelHandle(std::rc::Rc<CancelState>);
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+#[derive(Default)]
+struct CancelState {
+  cancelled:std::cell::Cell<bool>,
+  // the pending idle callback (or timeout) and the function resolving the promise awaiting it
+  pending:std::cell::RefCell<Option<(PendingCallback,leptos::web_sys::js_sys::Function)>>
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+enum PendingCallback {
+  Idle(u32),
+  Timeout(i32)
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+impl CancelHandle {
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Stops the hydration: the pending idle callback is cancelled, and no further replacements occur.
+  pub fn cancel(&self) {
+    self.0.cancelled.set(true);
+    if let Some((cb,resolve)) = self.0.pending.borrow_mut().take() {
+      let window = leptos::tachys::dom::window();
+      match cb {
+        PendingCallback::Idle(id) => window.cancel_idle_callback(id),
+        PendingCallback::Timeout(id) => window.clear_timeout_with_handle(id)
+      }
+      // resolve the awaited promise, so the hydration future can finish
+      let _ = resolve.call0(&leptos::wasm_bindgen::JsValue::NULL);
+    }
+  }
+
+  /// Whether [`cancel`](CancelHandle::cancel) has been called.
+  #[inline]
+  pub fn is_cancelled(&self) -> bool { self.0.cancelled.get() }
+}
+
+// Runs `f` under a new child owner of the current one, which lives as long as the current one.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn with_new_owner<T>(f:impl FnOnce() -> T) -> T {
+  let owner = Owner::new();
+  let ret = owner.with(f);
+  Owner::on_cleanup(move || drop(owner));
+  ret
+}
+
+// the callbacks registered via `on_cleanup_local` by key, and the last key used
+#[cfg(any(feature="csr",feature="hydrate"))]
+type LocalCleanups = (u64,std::collections::HashMap<u64,Box<dyn FnOnce()>>);
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+thread_local! {
+  static LOCAL_CLEANUPS : std::cell::RefCell<LocalCleanups> = Default::default();
+}
+
+// Like `Owner::on_cleanup`, but for callbacks that are not `Send`: `f` is kept in a thread-local map, and the
+// owner only gets its key. Like with `Owner::on_cleanup`, `f` is dropped right away if there is no owner.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn on_cleanup_local(f:impl FnOnce() + 'static) {
+  if Owner::current().is_none() { return }
+  let key = LOCAL_CLEANUPS.with_borrow_mut(|(last,map)| {
+    *last += 1;
+    map.insert(*last, Box::new(f));
+    *last
+  });
+  Owner::on_cleanup(move || {
+    // removed before calling it, since `f` may (indirectly) register or run other local cleanups
+    let f = LOCAL_CLEANUPS.with_borrow_mut(|(_,map)| map.remove(&key));
+    if let Some(f) = f { f() }
+  });
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn with_owner<T>(owner:&Option<Owner>,f:impl FnOnce() -> T) -> T {
+  match owner {
+    Some(o) => o.with(f),
+    None => f()
+  }
+}
+
+// resolves in the next idle period (or the next task, if `requestIdleCallback` is unavailable or `idle`
+// is `false`), or once `cancel` is cancelled
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) async fn yield_to_browser(cancel:&CancelHandle,idle:bool) {
+  use leptos::web_sys::js_sys::{Promise,Reflect};
+  let promise = Promise::new(&mut |resolve,_| {
+    let window = leptos::tachys::dom::window();
+    let cb = if idle && Reflect::has(&window, &"requestIdleCallback".into()).unwrap_or(false) {
+      window.request_idle_callback(&resolve).ok().map(PendingCallback::Idle)
+    } else {
+      window.set_timeout_with_callback(&resolve).ok().map(PendingCallback::Timeout)
+    };
+    *cancel.0.pending.borrow_mut() = cb.map(|cb| (cb,resolve));
+  });
+  let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+  cancel.0.pending.borrow_mut().take();
+}
+
+/// Like [`hydrate_node`], but aware of custom elements (Web Components): elements whose tag name contains a
+/// hyphen and that have not been upgraded yet (i.e. their definition has not been registered) are deferred
+/// until `customElements.whenDefined(tag)` resolves, and only then passed to `replace` (and traversed), so that
+/// `replace` sees fully-upgraded elements (e.g. with their shadow content) instead of placeholders.
+/// 
+/// Returns the number of elements replaced synchronously; deferred replacements happen under the reactive owner
+/// current when calling this function, and are dropped if the element has been removed from the DOM in the meantime.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_node_when_defined<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+Clone+'static
+>(node:Node,replace:&F) -> usize {
+  let deferred = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+  let count = {
+    let deferred = send_wrapper::SendWrapper::new(deferred.clone());
+    let opts = HydrateOpts::new().skip(move |e| {
+      let undefined = e.tag_name().contains('-') && !e.matches(":defined").unwrap_or(true);
+      if undefined { deferred.borrow_mut().push(e.clone()) }
+      undefined
+    });
+    hydrate_node_with(node, replace, &opts)
+  };
+  let owner = Owner::current();
+  let registry = leptos::tachys::dom::window().custom_elements();
+  for e in deferred.take() {
+    let Ok(promise) = registry.when_defined(&e.tag_name().to_ascii_lowercase()) else { continue };
+    let (replace,owner) = (replace.clone(),owner.clone());
+    wasm_bindgen_futures::spawn_local(async move {
+      let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+      if !e.is_connected() {return}
+      trace!("custom element defined tag={}",e.tag_name());
+      with_owner(&owner,|| hydrate_node_when_defined(e.into(), &replace));
+    });
+  }
+  count
+}
+
+/// Like [`hydrate_node`], but whether (and by what) an element is replaced is decided asynchronously, e.g. depending
+/// on a permission check on the server: If `decide` returns `Some(future)` for an element, the element is left
+/// untouched (and its subtree is not traversed) until the future resolves; then, if the future's result is
+/// `Some(r)`, the element is replaced by `r`, and otherwise kept, and its descendants are traversed.
+/// Elements for which `decide` returns `None` are traversed as usual.
+/// 
+/// Returns the number of elements whose decision is pending. The deferred replacements happen under the reactive
+/// owner current when calling this function, and are dropped if the element has been removed from the DOM in the meantime.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_node_async<
+  V:IntoView+'static,
+  R:FnOnce() -> V + 'static,
+  Fut:std::future::Future<Output = Option<R>> + 'static,
+  F:Fn(&Element) -> Option<Fut>+Clone+'static
+>(node:Node,decide:&F) -> usize {
+  hydrate_async(node, decide, true)
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn hydrate_async<
+  V:IntoView+'static,
+  R:FnOnce() -> V + 'static,
+  Fut:std::future::Future<Output = Option<R>> + 'static,
+  F:Fn(&Element) -> Option<Fut>+Clone+'static
+>(node:Node,decide:&F,include_root:bool) -> usize {
+  let pending = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+  {
+    let (pending,decide) = (send_wrapper::SendWrapper::new(pending.clone()),send_wrapper::SendWrapper::new(decide.clone()));
+    // elements with a pending decision are "skipped" for now
+    let opts = HydrateOpts::new().include_root(include_root).skip(move |e| match decide(e) {
+      Some(fut) => { pending.borrow_mut().push((e.clone(),fut)); true }
+      None => false
+    });
+    hydrate_node_with(node, &|_:&Element| None::<R>, &opts);
+  }
+  let pending = pending.take();
+  let count = pending.len();
+  let owner = Owner::current();
+  for (e,fut) in pending {
+    let (decide,owner) = (decide.clone(),owner.clone());
+    wasm_bindgen_futures::spawn_local(async move {
+      let r = fut.await;
+      if !e.is_connected() {return}
+      trace!("decided tag={} replace={}",e.tag_name(),r.is_some());
+      with_owner(&owner,|| match r {
+        Some(r) => if let Err(err) = replace_element(&e, r, &HydrateOpts::default()) {
+          leptos::logging::error!("Error replacing {}: {err}",e.tag_name());
+        },
+        None => { hydrate_async(e.into(), &decide, false); }
+      });
+    });
+  }
+  count
+}
+
+/// Like [`hydrate_node`], but only calls `replace` on the elements matching the CSS `selector`
+/// (using `querySelectorAll`), instead of walking the entire subtree.
+///
+/// Matches nested inside an element that has already been replaced are skipped - the replacement
+/// is responsible for hydrating its own children (e.g. via [`DomChildrenCont`](crate::DomChildrenCont)).
+/// Returns the number of replaced elements.
+///
+/// Panics if `selector` is invalid or a replacement fails; see [`try_hydrate_node_selector`] for a
+/// non-panicking version.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_node_selector<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,selector:&str,replace:&F) -> usize {
+  try_hydrate_node_selector(node, selector, replace).expect("Error hydrating node")
+}
+
+/// Like [`hydrate_node_selector`], but returns an error instead of panicking (e.g. for an invalid selector
+/// on third-party pages), and the number of replaced elements otherwise.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn try_hydrate_node_selector<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,selector:&str,replace:&F) -> Result<usize,HydrateError> {
+  if let Some(e) = node.dyn_ref::<Element>() {
+    if e.matches(selector)? {
+      if let Some(v) = replace(e) {
+        replace_element(e, v, &HydrateOpts::default())?;
+        return Ok(1)
+      }
+    }
+  }
   crate::cleanup(node.clone());
-  hydrate_children(node, replace);
+  let Some(matches) = query_selector_all(&node, selector)? else { return Ok(0) };
+  let opts = HydrateOpts::default();
+  let mut count = 0;
+  let mut i = 0;
+  while let Some(m) = matches.get(i) {
+    i += 1;
+    let Ok(e) = m.dyn_into::<Element>() else { continue };
+    if is_hydrated(&node,&e) { continue }
+    if let Some(v) = replace(&e) {
+      // descendants of `e` directly follow it in document order
+      while let Some(c) = matches.get(i) {
+        if !e.contains(Some(&c)) { break }
+        i += 1;
+      }
+      replace_element(&e, v, &opts)?;
+      count += 1;
+    }
+  }
+  Ok(count)
 }
 
-/// Iterates over the children of a node and replaces elements via the given function.
 #[cfg(any(feature="csr",feature="hydrate"))]
-pub(crate) fn hydrate_children<
+fn query_selector_all(node:&Node,selector:&str) -> Result<Option<leptos::web_sys::NodeList>,HydrateError> {
+  let r = if let Some(e) = node.dyn_ref::<Element>() {
+    e.query_selector_all(selector)
+  } else if let Some(f) = node.dyn_ref::<leptos::web_sys::DocumentFragment>() {
+    f.query_selector_all(selector)
+  } else if let Some(d) = node.dyn_ref::<leptos::web_sys::Document>() {
+    d.query_selector_all(selector)
+  } else { return Ok(None) };
+  Ok(Some(r?))
+}
+
+/// Like [`hydrate_node`], but only iterates over the *descendants* of `node`; `node` itself is never passed to
+/// `replace`. Returns the number of replaced elements.
+///
+/// Use this if `node` is a container that must stay in place (e.g. the element a component is mounted into, or
+/// one that `replace` would match itself); use [`hydrate_node`] if `node` may be replaced as well. Equivalent
+/// to [`hydrate_node_with`] with [`HydrateOpts::include_root`]`(false)`.
+///
+/// Panics if a replacement fails; see [`try_hydrate_children`] for a non-panicking version.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn hydrate_children<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(node:Node,replace:&F) -> usize {
+  try_hydrate_children(node, replace).expect("Error hydrating node")
+}
+
+/// Like [`hydrate_children`], but returns an error instead of panicking.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn try_hydrate_children<
   V:IntoView+'static,
   R:FnOnce() -> V,
   F:Fn(&Element) -> Option<R>+'static
->(node:Node,replace:&F) {
-  let Some(mut current) = node.first_child() else { return };
-  while let (_,Some(next)) = check_node(&current, &node, replace) {
+>(node:Node,replace:&F) -> Result<usize,HydrateError> {
+  try_hydrate_children_path(node, &mut Vec::new(), &mut |e:&Element,_:&[u32]| replace(e), &HydrateOpts::default())
+}
+
+/// Like [`hydrate_children`], but only traverses the parts of the DOM allowed by `opts`.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn hydrate_children_with<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>
+>(node:Node,replace:&F,opts:&HydrateOpts) {
+  try_hydrate_children_path(node, &mut Vec::new(), &mut |e:&Element,_:&[u32]| replace(e), opts).expect("Error hydrating node");
+}
+
+/// Like [`hydrate_children`], but `replace` additionally gets the child-index path of each element,
+/// starting at `path` (the path of `node` itself).
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn hydrate_children_path<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element,&[u32]) -> Option<R>
+>(node:Node,mut path:Vec<u32>,replace:&F) {
+  try_hydrate_children_path(node, &mut path, &mut |e:&Element,p:&[u32]| replace(e,p), &HydrateOpts::default()).expect("Error hydrating node");
+}
+
+/// Like [`hydrate_children_with`] for each of `nodes`, but yields to the browser (via `setTimeout`) whenever a
+/// synchronous slice of the traversal has taken `budget_ms` milliseconds or built `window` replacements,
+/// resuming in the next task (unless `cancel` has been cancelled in the meantime). Replacements are created
+/// under `owner`, since the current owner is not preserved across `.await`s.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) async fn hydrate_children_budgeted<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>
+>(
+  nodes:Vec<Node>,replace:&F,opts:&HydrateOpts,budget_ms:f64,window:usize,
+  owner:Option<Owner>,cancel:&CancelHandle
+) -> Result<usize,HydrateError> {
+  fn now() -> f64 {
+    leptos::tachys::dom::window().performance().map_or_else(leptos::web_sys::js_sys::Date::now,|p| p.now())
+  }
+  let mut replace = |e:&Element,_:&[u32]| replace(e);
+  let window = window.max(1);
+  let mut count = 0;
+  // the start of the current slice, and the number of replacements built in it
+  let mut start = now();
+  let mut built = 0;
+  for node in nodes {
+    let Some(mut current) = node.first_child() else { continue };
+    let mut path = vec![0];
+    loop {
+      if now() - start >= budget_ms || built >= window {
+        count += std::mem::take(&mut built);
+        yield_to_browser(cancel,false).await;
+        if cancel.is_cancelled() { return Ok(count) }
+        start = now();
+      }
+      let next = with_owner(&owner,|| loop {
+        let (replaced,next) = check_node(&current, &node, &mut path, &mut replace, opts)?;
+        built += replaced;
+        match next {
+          Some(n) if now() - start < budget_ms && built < window => current = n,
+          n => return Ok::<_,HydrateError>(n)
+        }
+      })?;
+      let Some(next) = next else { break };
+      current = next;
+    }
+  }
+  Ok(count + built)
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn try_hydrate_children_path<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:FnMut(&Element,&[u32]) -> Option<R>
+>(node:Node,path:&mut Vec<u32>,replace:&mut F,opts:&HydrateOpts) -> Result<usize,HydrateError> {
+  let mut count = 0;
+  let Some(mut current) = node.first_child() else { return Ok(0) };
+  path.push(0);
+  loop {
+    let (replaced,next) = check_node(&current, &node, path, replace, opts)?;
+    count += replaced;
+    let Some(next) = next else { return Ok(count) };
     current = next;
   }
 }
 
+// `path` is kept in sync with the returned node: the child indices (in the original DOM)
+// from `top` to the node.
 #[cfg(any(feature="csr",feature="hydrate"))]
-fn next(top:&Node,current: &Node) -> Option<Node> {
+fn next(top:&Node,current: &Node,path:&mut Vec<u32>) -> Option<Node> {
   if let Some(c) = current.first_child() {
+    path.push(0);
     return Some(c)
   }
-  next_non_child(top,current)
+  next_non_child(top,current,path)
 }
 
+// The next node in DFS order that is not a descendant of `current`. When replacing `current`, this is called
+// while `current` is still in place, so it never depends on the inserted nodes: for a first (or only) child,
+// the previous sibling (or the parent's first child) is never consulted; for a last (or only) child, the walk
+// continues at the nearest ancestor with a next sibling; and whitespace text siblings are regular nodes
+// (each advancing the index by one), so no sibling is skipped or visited twice.
 #[cfg(any(feature="csr",feature="hydrate"))]
-fn next_non_child(top:&Node,current: &Node) -> Option<Node> {
+fn next_non_child(top:&Node,current: &Node,path:&mut Vec<u32>) -> Option<Node> {
   if let Some(c) = current.next_sibling() {
+    if let Some(i) = path.last_mut() { *i += 1 }
     return Some(c)
   }
   let mut current = current.clone();
   loop {
     if let Some(p) = current.parent_node() {
+      path.pop();
       if p == *top {return None }
       if let Some(c) = p.next_sibling() {
+        if let Some(i) = path.last_mut() { *i += 1 }
         return Some(c)
       }
       current = p;
@@ -61,31 +968,582 @@ fn next_non_child(top:&Node,current: &Node) -> Option<Node> {
 fn check_node<
   V:IntoView+'static,
   R:FnOnce() -> V,
-  F:Fn(&Element) -> Option<R>+'static
->(node:&Node,top:&Node,replace:&F) -> (bool,Option<Node>) {
+  F:FnMut(&Element,&[u32]) -> Option<R>
+>(node:&Node,top:&Node,path:&mut Vec<u32>,replace:&mut F,opts:&HydrateOpts) -> Result<(usize,Option<Node>),HydrateError> {
   //leptos::logging::log!("Checking: {}",crate::prettyprint(node));
   if let Some(e) = node.dyn_ref::<Element>() {
-    if let Some(v) = replace(e) {
-      let p = e.parent_element().unwrap();
-      let next = e.next_sibling();
-      let ret = next_non_child(top, node);
-      //leptos::logging::log!("Triggered! Parent: {:?}",p.outer_html());
-      e.remove();
-      //leptos::logging::log!("Next: {:?}",next.as_ref().map(crate::prettyprint));
-      let owner = Owner::new();
-      owner.with(|| {
+    if opts.skips(e) {
+      trace!("skip tag={} path={path:?}",e.tag_name());
+      return Ok((0,next_non_child(top, node, path)))
+    }
+    if let Some(f) = opts.on_each.as_ref() { f(e) }
+    if let Some(range) = opts.range.as_ref().filter(|r| (r.start)(e)) {
+      trace!("replace range tag={} path={path:?}",e.tag_name());
+      let (group,consumed) = collect_range(e, &range.end)?;
+      let ret = match e.next_sibling() {
+        Some(n) => {
+          // skip the indices of the consumed siblings
+          if let Some(i) = path.last_mut() { *i += 1 + consumed }
+          Some(n)
+        }
+        None => next_non_child(top, node, path)
+      };
+      let v = (range.view)(group.into());
+      return Ok((opts.recover(replace_element(e, v, opts),node)?.into(),ret));
+    }
+    if opts.remove.as_ref().is_some_and(|f| f(e)) {
+      trace!("remove tag={} path={path:?}",e.tag_name());
+      let ret = next_non_child(top, node, path);
+      return Ok((opts.recover(remove_node(node),node)?.into(),ret));
+    }
+    if let Some(v) = replace(e,path) {
+      trace!("replace tag={} path={path:?}",e.tag_name());
+      // the continuation is determined *before* replacing `e`, so the traversal simply resumes at
+      // `e`'s former next sibling - without having to search for it among the inserted nodes.
+      let ret = next_non_child(top, node, path);
+      return Ok((opts.recover(replace_element(e, v, opts),node)?.into(),ret));
+    }
+    if opts.upgrade_noscript && e.tag_name().eq_ignore_ascii_case("noscript") {
+      trace!("upgrade noscript path={path:?}");
+      let template : leptos::web_sys::HtmlTemplateElement = leptos::prelude::document().create_element("template")?.unchecked_into();
+      template.set_inner_html(&e.text_content().unwrap_or_default());
+      let content = template.content();
+      // continue with the inserted nodes, which take the place of `e` - or after `e`, if there are none
+      let ret = match content.first_child() {
+        Some(first) => Some(first),
+        None => next_non_child(top, node, path)
+      };
+      e.parent_node().ok_or(HydrateError::DetachedNode)?.replace_child(&content, node)?;
+      return Ok((0,ret))
+    }
+    trace!("visit tag={} path={path:?}",e.tag_name());
+    if opts.descend_if.as_ref().is_some_and(|f| !f(e)) {
+      return Ok((0,next_non_child(top, node, path)))
+    }
+    // the contents of a `<template>` are not its children, but live in a separate fragment
+    if let Some(t) = e.dyn_ref::<leptos::web_sys::HtmlTemplateElement>().filter(|_| opts.traverse_templates && opts.descends(path.len())) {
+      let len = path.len();
+      let count = try_hydrate_children_path(t.content().into(), path, replace, opts)?;
+      path.truncate(len);
+      return Ok((count,next_non_child(top, node, path)))
+    }
+  } else if let (Some(t),Some(f)) = (
+    node.dyn_ref::<leptos::web_sys::Text>().filter(|t| !opts.skip_whitespace_text || !is_whitespace(t)),
+    opts.replace_text.as_ref()
+  ) {
+    if let Some(v) = f(t) {
+      trace!("replace text path={path:?}");
+      let ret = next_non_child(top, node, path);
+      return Ok((opts.recover(replace_element(node, v, opts),node)?.into(),ret));
+    }
+  } else if let (Some(c),Some(f)) = (node.dyn_ref::<leptos::web_sys::Comment>(),opts.replace_comment.as_ref()) {
+    if let Some(v) = f(c) {
+      trace!("replace comment path={path:?}");
+      let ret = next_non_child(top, node, path);
+      return Ok((opts.recover(replace_element(node, v, opts),node)?.into(),ret));
+    }
+  }
+  if !opts.descends(path.len()) {
+    return Ok((0,next_non_child(top, node, path)))
+  }
+  Ok((0,next(top,node,path)))
+}
+
+/// Attribute identifying the keyed elements of [`DomStringContReactive`](crate::DomStringContReactive).
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) const KEY_ATTR: &str = "data-key";
+
+// The replacements of keyed elements (see `DomStringContReactive`), which are kept alive across
+// re-hydrations as long as their keys are present.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) struct KeyedInstances {
+  // the instances' owners are children of this one (rather than of e.g. an effect's owner, which would
+  // clean them up on every run)
+  parent:Option<Owner>,
+  instances:std::collections::HashMap<String,(Owner,Vec<Node>)>
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+impl KeyedInstances {
+  pub(crate) fn new() -> Self {
+    Self { parent:Owner::current(), instances:std::collections::HashMap::new() }
+  }
+
+  /// Removes the nodes of all instances from the DOM, keeping their state.
+  pub(crate) fn detach(&self) {
+    for (_,nodes) in self.instances.values() {
+      for n in nodes { let _ = remove_node(n); }
+    }
+  }
+
+  /// Cleans up all instances.
+  pub(crate) fn clear(&mut self) {
+    for (owner,_) in std::mem::take(&mut self.instances).into_values() { owner.cleanup() }
+  }
+
+  /// Replaces the (top-most) keyed elements below `root`: those whose key has an instance already by the
+  /// instance's nodes, the others by a new instance (if `replace` returns a view for them). Instances whose
+  /// key is not present anymore are cleaned up. Only the first element with a given key is keyed; later
+  /// duplicates are left in place (to be hydrated like unkeyed elements).
+  pub(crate) fn hydrate<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>
+  >(&mut self,root:&Element,replace:&F) -> Result<(),HydrateError> {
+    let mut old = std::mem::take(&mut self.instances);
+    let keyed = root.query_selector_all(&format!("[{KEY_ATTR}]"))?;
+    for i in 0..keyed.length() {
+      let Some(e) = keyed.get(i).and_then(|n| n.dyn_into::<Element>().ok()) else { continue };
+      // nested keyed elements are up to the outer one
+      if e.parent_element().and_then(|p| p.closest(&format!("[{KEY_ATTR}]")).ok().flatten())
+        .is_some_and(|a| root.contains(Some(&a))) {continue}
+      if !root.contains(Some(&e)) {continue}
+      let Some(key) = e.get_attribute(KEY_ATTR) else { continue };
+      if self.instances.contains_key(&key) {
+        leptos::logging::warn!("Duplicate {KEY_ATTR}=\"{key}\"; hydrating it without a key");
+        continue
+      }
+      let p = e.parent_node().ok_or(HydrateError::DetachedNode)?;
+      if let Some(instance) = old.remove(&key) {
+        trace!("keep key={key}");
+        for n in &instance.1 { p.insert_before(n, Some(&e))?; }
+        e.remove();
+        self.instances.insert(key,instance);
+      } else if let Some(v) = replace(&e) {
+        trace!("build key={key}");
+        let owner = with_owner(&self.parent,Owner::new);
+        let fragment = leptos::prelude::document().create_document_fragment();
+        owner.with(|| {
           let mut r = v().into_view().build();
-          if let Some(e) = next.as_ref() {
-            e.insert_before_this(&mut r);
-          } else {
-            r.mount(&p,None);
-          }
+          r.mount(fragment.unchecked_ref(),None);
           let mut r = send_wrapper::SendWrapper::new(r);
-          Owner::on_cleanup(|| {r.unmount();drop(r)});
-      });
-      Owner::on_cleanup(move || drop(owner));
-      return (true,ret);
+          Owner::on_cleanup(move || { r.unmount(); drop(r) });
+        });
+        let nodes : Vec<Node> = std::iter::successors(fragment.first_child(),|c| c.next_sibling()).collect();
+        for n in &nodes {
+          if let Some(e) = n.dyn_ref::<Element>() { let _ = e.set_attribute(HYDRATED_MARKER, ""); }
+        }
+        p.insert_before(&fragment, Some(&e))?;
+        e.remove();
+        self.instances.insert(key,(owner,nodes));
+      }
+    }
+    for (_key,(owner,_)) in old {
+      trace!("drop key={_key}");
+      owner.cleanup();
+    }
+    Ok(())
+  }
+}
+
+// Replaces (only) `e`, if `replace` returns a view for it.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn replace_single<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>
+>(e:&Element,replace:&F) -> Result<bool,HydrateError> {
+  replace_root(e, &[], &mut |e:&Element,_:&[u32]| replace(e), &HydrateOpts::default())
+}
+
+// Replaces the root of a hydration pass, if `replace` returns a view for it.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn replace_root<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:FnMut(&Element,&[u32]) -> Option<R>
+>(node:&Node,path:&[u32],replace:&mut F,opts:&HydrateOpts) -> Result<bool,HydrateError> {
+  let Some(e) = node.dyn_ref::<Element>() else { return Ok(false) };
+  if opts.remove.as_ref().is_some_and(|f| f(e)) {
+    return opts.recover(remove_node(node),node)
+  }
+  let Some(v) = replace(e,path) else { return Ok(false) };
+  opts.recover(replace_element(e, v, opts),node)
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn is_whitespace(t:&leptos::web_sys::Text) -> bool {
+  t.data().chars().all(char::is_whitespace)
+}
+
+// Moves the siblings following `start` up to (excluding) the next element matching `end` into a new (detached)
+// `<div>`, and removes that element; returns the `<div>` and the number of removed siblings.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn collect_range(start:&Element,end:&Predicate) -> Result<(Element,u32),HydrateError> {
+  let group = leptos::prelude::document().create_element("div")?;
+  let mut consumed = 0;
+  while let Some(n) = start.next_sibling() {
+    consumed += 1;
+    if n.dyn_ref::<Element>().is_some_and(|e| end(e)) {
+      remove_node(&n)?;
+      break
+    }
+    group.append_child(&n)?;
+  }
+  Ok((group,consumed))
+}
+
+// Removes `e` from the DOM for good.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn remove_node(e:&Node) -> Result<(),HydrateError> {
+  let p = e.parent_node().ok_or(HydrateError::DetachedNode)?;
+  p.remove_child(e)?;
+  Ok(())
+}
+
+// Removes `e` (an element, text or comment node) from the DOM and mounts the view returned by `v` in its place.
+// If that fails, the view is cleaned up again, and `e` is put back.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn replace_element<
+  V:IntoView+'static,
+  R:FnOnce() -> V
+>(e:&Node,v:R,opts:&HydrateOpts) -> Result<(),HydrateError> {
+  // the parent may also be a `ShadowRoot`, which is not an element
+  let p = e.parent_node().ok_or(HydrateError::DetachedNode)?;
+  let prev = e.previous_sibling();
+  let next = e.next_sibling();
+  let owner = Owner::new();
+  if let (Some(f),Some(e)) = (opts.on_before_remove.as_ref(),e.dyn_ref::<Element>()) {
+    owner.with(|| f(e));
+  }
+  // the id of the focused element, if it is (within) `e`
+  let focused = if opts.preserve_focus {
+    leptos::prelude::document().active_element().filter(|a| e.contains(Some(a))).map(|a| a.id())
+  } else { None };
+  //leptos::logging::log!("Triggered! Parent: {:?}",p.outer_html());
+  // `e` is removed first, since the view may reinsert it itself (see `wrap_original`)
+  p.remove_child(e)?;
+  //leptos::logging::log!("Next: {:?}",next.as_ref().map(crate::prettyprint));
+  let mounted = owner.with(|| {
+      let mut r = v().into_view().build();
+      // mount into a fragment first, so that the live DOM is only touched once (rather than once per
+      // top-level node of the view). Like for a `ShadowRoot`, only `Node` methods are used on the parent.
+      let fragment = leptos::prelude::document().create_document_fragment();
+      r.mount(fragment.unchecked_ref(),None);
+      let mounted = p.insert_before(&fragment, next.as_ref()).is_ok();
+      #[cfg(feature="leak-check")]
+      let live = LiveReplacement::new();
+      // the view is unmounted (removing its nodes from the DOM) before its state is dropped
+      let teardown = move || {
+        r.unmount();
+        drop(r);
+        #[cfg(feature="leak-check")]
+        drop(live);
+      };
+      if opts.local {
+        on_cleanup_local(teardown);
+      } else {
+        let teardown = send_wrapper::SendWrapper::new(teardown);
+        Owner::on_cleanup(move || teardown.take()());
+      }
+      mounted
+  });
+  if !mounted {
+    trace!("mount failed node={}",e.node_name());
+    // unmounts the view (and hence `e`, if the view has taken it) before putting `e` back
+    owner.cleanup();
+    let _ = p.insert_before(e, next.as_ref());
+    return Err(HydrateError::MountFailed)
+  }
+  Owner::on_cleanup({let owner = owner.clone(); move || drop(owner)});
+  let first = match prev { Some(p) => p.next_sibling(), None => p.first_child() }
+    .filter(|f| next.as_ref() != Some(f));
+  // mark the top-level elements of the replacement, so they can be recognized later
+  let mut current = first.clone();
+  while let Some(c) = current {
+    if next.as_ref() == Some(&c) { break }
+    if let Some(e) = c.dyn_ref::<Element>() {
+      let _ = e.set_attribute(HYDRATED_MARKER, "");
+    }
+    current = c.next_sibling();
+  }
+  if let Some(id) = focused {
+    restore_focus(first.clone(), next.as_ref(), &id, opts.focus_selector.as_deref());
+  }
+  if let (Some(roots),Some(first)) = (opts.roots.as_ref(),first.as_ref()) {
+    roots.push(first.clone());
+  }
+  if let (Some(f),Some(e),Some(first)) = (opts.on_mounted.as_ref(),e.dyn_ref::<Element>(),first) {
+    owner.with(|| f(e,&first));
+  }
+  Ok(())
+}
+
+// Focuses the element with the given `id` (if non-empty), or otherwise the first one matching `selector`,
+// among the nodes from `first` up to `end` (exclusive) and their descendants.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn restore_focus(first:Option<Node>,end:Option<&Node>,id:&str,selector:Option<&str>) {
+  let by_id = (!id.is_empty()).then(|| format!("[id=\"{}\"]",id.replace('\\',"\\\\").replace('"',"\\\"")));
+  for sel in by_id.iter().map(String::as_str).chain(selector) {
+    let mut current = first.clone();
+    while let Some(c) = current {
+      if end == Some(&c) { break }
+      if let Some(e) = c.dyn_ref::<Element>() {
+        let target = if e.matches(sel).unwrap_or(false) { Some(e.clone()) } else { e.query_selector(sel).ok().flatten() };
+        if let Some(t) = target.and_then(|t| t.dyn_into::<leptos::web_sys::HtmlElement>().ok()) {
+          let _ = t.focus();
+          return
+        }
+      }
+      current = c.next_sibling();
+    }
+  }
+}
+
+#[cfg(feature="leak-check")]
+static LIVE_REPLACEMENTS : std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// The number of replacement views currently alive, i.e. mounted and not yet cleaned up
+/// (requires the `leak-check` feature).
+///
+/// After unmounting everything hydrated (e.g. via [`HydrationHandle::unmount`](crate::HydrationHandle::unmount)),
+/// this should be back to zero; otherwise, some reactive owner has not been cleaned up.
+#[cfg(feature="leak-check")]
+pub fn live_replacement_count() -> usize {
+  LIVE_REPLACEMENTS.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+// Counts as a live replacement until dropped.
+#[cfg(all(feature="leak-check",any(feature="csr",feature="hydrate")))]
+struct LiveReplacement;
+
+#[cfg(all(feature="leak-check",any(feature="csr",feature="hydrate")))]
+impl LiveReplacement {
+  fn new() -> Self {
+    LIVE_REPLACEMENTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    Self
+  }
+}
+
+#[cfg(all(feature="leak-check",any(feature="csr",feature="hydrate")))]
+impl Drop for LiveReplacement {
+  fn drop(&mut self) {
+    LIVE_REPLACEMENTS.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+  }
+}
+
+/// An element that [`dry_run_hydrate`] found would be replaced.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct HydrateMatch {
+  /// The child-index path from the root of the traversal to the element (see [`DomChildrenContPath`](crate::DomChildrenContPath)).
+  pub path:Vec<u32>,
+  /// A short CSS-like description of the element: its tag name, id and classes, e.g. `div#main.card.wide`.
+  pub selector:String,
+  /// The beginning of the outer HTML of the element (at most 120 characters).
+  pub snippet:String
+}
+
+/// Walks the DOM like [`hydrate_node`] (skipping replacements inserted by a previous pass), but only reports
+/// which elements `predicate` would replace, without building any views or mutating the DOM; e.g. to debug
+/// the rules of an embedded script on an unfamiliar page. Like with [`hydrate_node`], the descendants of a
+/// matching element are not visited.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn dry_run_hydrate(node:Node,predicate:impl Fn(&Element) -> bool) -> Vec<HydrateMatch> {
+  const SNIPPET_LEN: usize = 120;
+  let mut ret = Vec::new();
+  // whether the traversal should not descend into `n`
+  let mut check = |n:&Node,path:&[u32]| {
+    let Some(e) = n.dyn_ref::<Element>() else { return false };
+    if e.has_attribute(HYDRATED_MARKER) { return true }
+    if !predicate(e) { return false }
+    let mut selector = e.tag_name().to_ascii_lowercase();
+    let id = e.id();
+    if !id.is_empty() { selector.push('#'); selector.push_str(&id) }
+    for c in e.class_name().split_whitespace() { selector.push('.'); selector.push_str(c) }
+    let snippet = e.outer_html().chars().take(SNIPPET_LEN).collect();
+    ret.push(HydrateMatch { path:path.to_vec(), selector, snippet });
+    true
+  };
+  if check(&node,&[]) { return ret }
+  let mut path = vec![0];
+  let mut current = node.first_child();
+  while let Some(n) = current {
+    current = if check(&n,&path) { next_non_child(&node, &n, &mut path) } else { next(&node, &n, &mut path) };
+  }
+  ret
+}
+
+/// Serializes the (current, e.g. post-hydration) subtree of `node` to an HTML string: the outer HTML
+/// of elements, the (escaped) text of text nodes, and the concatenated children of anything else (e.g. document
+/// fragments or shadow roots). Useful for snapshotting the result of a hydration pass, e.g. in tests.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn serialize_node(node:&Node) -> String {
+  match node.node_type() {
+    Node::ELEMENT_NODE => node.unchecked_ref::<Element>().outer_html(),
+    Node::TEXT_NODE => node.text_content().unwrap_or_default()
+      .replace('&',"&amp;").replace('<',"&lt;").replace('>',"&gt;"),
+    Node::COMMENT_NODE => format!("<!--{}-->",node.text_content().unwrap_or_default()),
+    _ => std::iter::successors(node.first_child(),|c| c.next_sibling())
+      .map(|c| serialize_node(&c)).collect()
+  }
+}
+
+/// Parses `html` as a full document of the given MIME type via a `DOMParser`, and appends (copies of) the
+/// children of its `<body>` (or of its root element, if there is no body) to `e`.
+///
+/// XML parsers report malformed input by a `<parsererror>` element in the resulting document; in that
+/// case, nothing is appended and its message is returned as an error.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn parse_into(e:&Element,html:&str,mime:&str) -> Result<(),HydrateError> {
+  let tp = leptos::web_sys::SupportedType::from_js_value(&mime.into())
+    .ok_or_else(|| HydrateError::Dom(format!("unsupported MIME type {mime}")))?;
+  let doc = leptos::web_sys::DomParser::new()?.parse_from_string(html, tp)?;
+  if let Some(err) = doc.get_elements_by_tag_name("parsererror").item(0) {
+    let msg = err.text_content().unwrap_or_default();
+    return Err(HydrateError::Dom(format!("parser error: {}",msg.trim())))
+  }
+  let Some(root) = doc.body().map(Element::from).or_else(|| doc.document_element()) else { return Ok(()) };
+  let document = leptos::prelude::document();
+  let mut current = root.first_child();
+  while let Some(c) = current {
+    current = c.next_sibling();
+    e.append_child(&document.import_node_with_deep(&c, true)?)?;
+  }
+  Ok(())
+}
+
+/// Parses `html` (via a `<template>`, so that e.g. `<tr>`s are kept) and appends the resulting nodes to `e`,
+/// hydrating each of them (including the top-level ones). Returns the number of replaced elements.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn append_and_hydrate<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(e:&Element,html:&str,replace:&F) -> Result<usize,HydrateError> {
+  let template : leptos::web_sys::HtmlTemplateElement = leptos::prelude::document().create_element("template")?.unchecked_into();
+  template.set_inner_html(html);
+  let content = template.content();
+  let mut nodes = Vec::new();
+  let mut current = content.first_child();
+  while let Some(c) = current {
+    current = c.next_sibling();
+    nodes.push(c);
+  }
+  e.append_child(&content)?;
+  let mut count = 0;
+  for n in nodes {
+    // a replacement of a previous node may have moved it elsewhere already
+    if n.parent_node().as_ref() != Some(e) { continue }
+    count += try_hydrate_node(n, replace)?;
+  }
+  Ok(count)
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+const VOID_ELEMENTS : [&str;14] = ["area","base","br","col","embed","hr","img","input","link","meta","param","source","track","wbr"];
+#[cfg(any(feature="csr",feature="hydrate"))]
+const RAW_TEXT_ELEMENTS : [&str;5] = ["script","style","textarea","title","xmp"];
+
+/// The length of the longest prefix of (a chunk of) `html` consisting only of complete top-level nodes, i.e.
+/// which can be parsed without cutting off an element whose end tag has not arrived yet. Trailing text is
+/// not included either, since it may continue in the next chunk.
+///
+/// This is a heuristic based on matching start and end tags: Elements whose end tags are optional (e.g. `<p>`
+/// or `<li>` without `</p>`/`</li>`) keep everything following them at a nonzero depth.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn complete_prefix(html:&str) -> usize {
+  // index of the `>` closing the tag starting at `tag[0]`, skipping quoted attribute values
+  fn tag_end(tag:&str) -> Option<usize> {
+    let mut quote = None;
+    for (i,c) in tag.char_indices().skip(1) {
+      match (quote,c) {
+        (None,'"' | '\'') => quote = Some(c),
+        (Some(q),c) if q == c => quote = None,
+        (None,'>') => return Some(i),
+        _ => ()
+      }
+    }
+    None
+  }
+  let (mut i,mut depth,mut safe) = (0,0usize,0);
+  while let Some(off) = html[i..].find('<') {
+    let start = i + off;
+    let rest = &html[start..];
+    if rest.starts_with("<!--") {
+      let Some(end) = rest.find("-->") else { break };
+      i = start + end + 3;
+    } else if rest.starts_with("</") || rest.starts_with("<!") || rest.starts_with("<?") {
+      let Some(end) = rest.find('>') else { break };
+      if rest.starts_with("</") { depth = depth.saturating_sub(1) }
+      i = start + end + 1;
+    } else if rest[1..].starts_with(|c:char| c.is_ascii_alphabetic()) {
+      let Some(end) = tag_end(rest) else { break };
+      i = start + end + 1;
+      let name = rest[1..].split(|c:char| c.is_ascii_whitespace() || c == '>' || c == '/')
+        .next().unwrap_or_default().to_ascii_lowercase();
+      if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+        // the content of e.g. `<script>` is not markup; it ends with the first matching end tag
+        let Some(close) = html[i..].to_ascii_lowercase().find(&format!("</{name}")) else { break };
+        let Some(end) = html[i + close..].find('>') else { break };
+        i += close + end + 1;
+      } else if !VOID_ELEMENTS.contains(&name.as_str()) && !rest[..end].ends_with('/') {
+        depth += 1;
+      }
+    } else {
+      // a literal `<` in text
+      i = start + 1;
+      continue
+    }
+    if depth == 0 { safe = i }
+  }
+  safe
+}
+
+/// Replaces every `<script>` in `root` by a fresh copy, so that the browser executes it
+/// (scripts inserted via `innerHTML` are never run).
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn run_scripts(root:&Element) -> Result<(),HydrateError> {
+  let scripts = root.query_selector_all("script")?;
+  let document = leptos::prelude::document();
+  for i in 0..scripts.length() {
+    let Some(old) = scripts.get(i).and_then(|n| n.dyn_into::<Element>().ok()) else { continue };
+    let new = document.create_element("script")?;
+    for name in old.get_attribute_names().iter() {
+      let Some(name) = name.as_string() else { continue };
+      if let Some(value) = old.get_attribute(&name) {
+        new.set_attribute(&name, &value)?;
+      }
     }
+    new.set_text_content(old.text_content().as_deref());
+    old.replace_with_with_node_1(&new)?;
+  }
+  Ok(())
+}
+
+/// Moves the children of `e` in front of it and removes `e`; the moved children are removed from the DOM
+/// again when the current owner is cleaned up.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn unwrap_element(e:&Element) -> Result<(),HydrateError> {
+  let p = e.parent_node().ok_or(HydrateError::DetachedNode)?;
+  while let Some(c) = e.first_child() {
+    p.insert_before(&c, Some(e))?;
+    crate::cleanup(c);
+  }
+  e.remove();
+  Ok(())
+}
+
+pub(crate) const MATHML_NS: &str = "http://www.w3.org/1998/Math/MathML";
+pub(crate) const SVG_NS: &str = "http://www.w3.org/2000/svg";
+
+/// Makes sure the children of the MathML element `e` are in the MathML namespace, by re-parsing them
+/// within a `<math>` element if necessary (e.g. if the HTML was set in a context where it has been
+/// parsed as HTML, resulting in unknown HTML elements `<mi>`, `<mn>`, etc.).
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub(crate) fn fix_mathml_namespace(e:&Element) -> Result<(),HydrateError> {
+  let mut child = e.first_element_child();
+  let mut html_children = false;
+  while let Some(c) = child {
+    if c.namespace_uri().as_deref() != Some(MATHML_NS) { html_children = true; break }
+    child = c.next_element_sibling();
+  }
+  if !html_children { return Ok(()) }
+  let div = leptos::prelude::document().create_element("div")?;
+  div.set_inner_html(&format!("<math>{}</math>",e.inner_html()));
+  let Some(math) = div.first_element_child() else { return Ok(()) };
+  e.set_inner_html("");
+  while let Some(c) = math.first_child() {
+    e.append_child(&c)?;
   }
-  (false,next(top,node))
-}
\ No newline at end of file
+  Ok(())
+}
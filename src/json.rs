@@ -0,0 +1,171 @@
+//! Server-driven UI: rendering a JSON description of a DOM tree (requires the `json` feature).
+
+use leptos::prelude::*;
+#[cfg(any(feature="csr",feature="hydrate"))]
+use leptos::web_sys::Element;
+#[cfg(any(feature="csr",feature="hydrate"))]
+use crate::{dom, OriginalNode};
+
+/// A node of the JSON tree rendered by [`DomJsonCont`]; one of
+/// - a string, rendered as a text node: `"Hello"`,
+/// - a component, rendered via the [`ComponentFactory`]:
+///   `{"component":"Chart","props":{"series":[1,2,3]},"children":[...]}`,
+/// - an element: `{"tag":"div","attrs":{"class":"card"},"children":[...]}`.
+///
+/// `props`, `attrs` and `children` are optional.
+#[derive(Clone,Debug,PartialEq,serde::Deserialize)]
+#[serde(untagged)]
+pub enum JsonNode {
+  Text(String),
+  Component {
+    component:String,
+    #[serde(default)]
+    props:serde_json::Value,
+    #[serde(default)]
+    children:Vec<JsonNode>
+  },
+  Element {
+    tag:String,
+    #[serde(default)]
+    attrs:std::collections::BTreeMap<String,String>,
+    #[serde(default)]
+    children:Vec<JsonNode>
+  }
+}
+
+/// Maps the component nodes of a [`JsonNode`] tree to views.
+pub trait ComponentFactory {
+  /// The view for the component `key` with the given `props`. `children` is the (hydrated) view of the node's
+  /// children, to be inserted wherever the component renders its children. For unknown keys, implementations
+  /// should return some fallback, e.g. just `children`.
+  fn create(&self,key:&str,props:&serde_json::Value,children:AnyView) -> AnyView;
+}
+
+impl<F:Fn(&str,&serde_json::Value,AnyView) -> AnyView> ComponentFactory for F {
+  #[inline]
+  fn create(&self,key:&str,props:&serde_json::Value,children:AnyView) -> AnyView {
+    self(key,props,children)
+  }
+}
+
+// the placeholder elements for components, referencing their `(key,props)` in `JsonState::components`
+#[cfg(any(feature="csr",feature="hydrate"))]
+const PLACEHOLDER_TAG: &str = "leptos-json-component";
+#[cfg(any(feature="csr",feature="hydrate"))]
+const INDEX_ATTR: &str = "data-index";
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+struct JsonState {
+  factory:Box<dyn ComponentFactory>,
+  components:std::cell::RefCell<Vec<Option<(String,serde_json::Value)>>>
+}
+
+#[cfg(any(feature="csr",feature="hydrate"))]
+#[derive(Clone)]
+struct JsonContext(send_wrapper::SendWrapper<std::rc::Rc<JsonState>>);
+
+/// A component that renders the [`JsonNode`] tree described by `json` (e.g. returned by the server), using
+/// `factory` for the component nodes:
+/// ```ignore
+/// let factory = |key:&str, props:&serde_json::Value, children:AnyView| match key {
+///   "Chart" => view!(<Chart series=props["series"].clone()/>).into_any(),
+///   _ => children
+/// };
+/// view!(<DomJsonCont json factory/>)
+/// ```
+/// The elements are built into a `<wrapper_tag>` element (default: `<div>`), with placeholders for the
+/// components, which are then replaced like any other element by [`hydrate_children`](crate::hydrate_children);
+/// each under its own reactive owner. If the JSON is invalid, an error is logged and nothing is rendered.
+///
+/// The tree is only built client-side; server-side, the wrapper stays empty.
+///
+/// Unless `trusted` is set, `<script>` elements (which would run when inserted) and event handler attributes
+/// (`on*`) are dropped from the tree, with a warning. This is no general-purpose sanitizer though (e.g.
+/// `javascript:` URLs or `<iframe srcdoc>` are kept as is): JSON from untrusted sources should be validated
+/// before, just like HTML passed to [`DomStringCont`](crate::DomStringCont) (see its `sanitize` prop).
+#[component]
+pub fn DomJsonCont<F:ComponentFactory+'static>(
+  json:String,factory:F,#[prop(optional)] on_load:Option<RwSignal<bool>>,
+  #[prop(optional,into)] wrapper_tag:Option<String>,#[prop(optional)] trusted:bool
+) -> impl IntoView {
+  let tag = wrapper_tag.unwrap_or_else(|| "div".to_string());
+  let rf = NodeRef::<leptos::html::Custom<String>>::new();
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  {
+    let state = std::rc::Rc::new(JsonState { factory:Box::new(factory), components:std::cell::RefCell::new(Vec::new()) });
+    provide_context(JsonContext(send_wrapper::SendWrapper::new(state.clone())));
+    let owner = Owner::current();
+    rf.on_load(move |e| {
+      use leptos::wasm_bindgen::JsCast;
+      let e : Element = e.unchecked_into();
+      let node = match serde_json::from_str::<JsonNode>(&json) {
+        Ok(node) => node,
+        Err(err) => {
+          leptos::logging::error!("Error parsing JSON: {err}");
+          return
+        }
+      };
+      if let Err(err) = build(node, &e, &mut state.components.borrow_mut(), trusted) {
+        leptos::logging::error!("Error building JSON tree: {err}");
+        return
+      }
+      let hydrate = || { dom::hydrate_children(e.into(), &replace_json); };
+      match owner { Some(o) => o.with(hydrate), None => hydrate() }
+      if let Some(on_load) = on_load { on_load.set(true); }
+    });
+  }
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (json,factory,on_load,trusted);
+  leptos::html::custom(tag).node_ref(rf)
+}
+
+// Appends the DOM nodes described by `node` to `parent`, with placeholders for components. Unless `trusted`,
+// scripts and event handler attributes are dropped.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn build(node:JsonNode,parent:&Element,components:&mut Vec<Option<(String,serde_json::Value)>>,trusted:bool) -> Result<(),crate::HydrateError> {
+  let document = leptos::prelude::document();
+  let (e,children) = match node {
+    JsonNode::Text(t) => {
+      parent.append_child(&document.create_text_node(&t))?;
+      return Ok(())
+    }
+    JsonNode::Element { tag, .. } if !trusted && tag.eq_ignore_ascii_case("script") => {
+      leptos::logging::warn!("Dropping <script> from untrusted JSON");
+      return Ok(())
+    }
+    JsonNode::Element { tag, attrs, children } => {
+      let e = document.create_element(&tag)?;
+      for (k,v) in attrs {
+        if !trusted && k.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("on")) {
+          leptos::logging::warn!("Dropping attribute {k} from untrusted JSON");
+          continue
+        }
+        e.set_attribute(&k, &v)?;
+      }
+      (e,children)
+    }
+    JsonNode::Component { component, props, children } => {
+      let e = document.create_element(PLACEHOLDER_TAG)?;
+      e.set_attribute(INDEX_ATTR, &components.len().to_string())?;
+      components.push(Some((component,props)));
+      (e,children)
+    }
+  };
+  for c in children { build(c, &e, components, trusted)?; }
+  parent.append_child(&e)?;
+  Ok(())
+}
+
+// A named function (rather than a closure), so that it can pass itself on to the children of components.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn replace_json(e:&Element) -> Option<impl FnOnce() -> AnyView> {
+  if !e.tag_name().eq_ignore_ascii_case(PLACEHOLDER_TAG) { return None }
+  let index : usize = e.get_attribute(INDEX_ATTR)?.parse().ok()?;
+  let ctx = use_context::<JsonContext>()?;
+  let (key,props) = ctx.0.components.borrow_mut().get_mut(index)?.take()?;
+  let orig = OriginalNode::from(e.clone());
+  Some(move || {
+    let children = orig.children_into_view_cont(replace_json, None).into_any();
+    ctx.0.factory.create(&key, &props, children)
+  })
+}
@@ -12,6 +12,8 @@
  * ## CSR Example
  * Say we want to replace all elements with the attribute `data-replace-with-leptos` with a leptos component `MyReplacementComponent`, that simply wraps the original children in a `div` with a solid red border. This component would roughly look like this:
  * ```
+ * # use leptos::prelude::*;
+ * # use leptos_dyn_dom::*;
  * #[component]
  * fn MyReplacementComponent(orig:OriginalNode) -> impl IntoView {
  *    view! {
@@ -21,15 +23,19 @@
  *   }
  * }
  * ```
- * This component takes an `orig:`[`OriginalChildren`] that represents the "children the original node used to have". They get reinserted where we use the [`DomChildren`] component - i.e. wrapped in a `div` with a red border.
+ * This component takes an `orig:`[`OriginalNode`] that represents the "children the original node used to have". They get reinserted where we use the [`DomChildren`] component - i.e. wrapped in a `div` with a red border.
  * 
  * So, where do we get `orig` from? 
- * - If we already have an `e:&`[`Element`], we can simply call [`OriginalChildren::new`]`(e)`. That will immediately remove *all* children of `e` and move them into the returned [`OriginalChildren`]. Trouble then is, that the component likely doesn't know where in leptos' reactive graph it should be inserted regarding reactivity (i.e. inheriting context and all that).
+ * - If we already have an `e:&`[`Element`], we can simply call [`OriginalNode`]`::from(e.clone())`; the children of `e` are then moved to wherever the returned [`OriginalNode`] is rendered (e.g. via [`DomChildren`]). Trouble then is, that the component likely doesn't know where in leptos' reactive graph it should be inserted regarding reactivity (i.e. inheriting context and all that).
  * - More likely, we don't have an [`Element`] yet. Moreover, we probably want to iterate over the entire body *once* to find all nodes we want to make reactive, and we also need to set up a global reactive system for all our inserted components.
  * 
- * To do that, we call [`hydrate_body`] (requires the `csr` feature flag) with a function that takes the [`OriginalChildren`] of the body and returns some leptos view; e.g.:
+ * To do that, we call [`hydrate_body`] (requires the `csr` feature flag) with a function that takes the [`OriginalNode`] of the body and returns some leptos view; e.g.:
  * 
  * ```
+ * # use leptos::prelude::*;
+ * # use leptos_dyn_dom::*;
+ * # use leptos::wasm_bindgen::{self,prelude::*};
+ * # mod console_error_panic_hook { pub fn set_once() {} }
  *  #[component]
  *  fn MainBody(orig:OriginalNode) -> impl IntoView {
  *     // set up some signals, provide context etc.
@@ -37,10 +43,11 @@
  *       <DomChildren orig/>
  *     }
  *  }
+ * # #[cfg(feature="csr")]
  *  #[wasm_bindgen(start)]
  *   pub fn run() {
  *       console_error_panic_hook::set_once();
- *       hydrate_body(|orig| view!(<MainBody orig/>).into_any())
+ *       hydrate_body(|orig| view!(<MainBody orig/>).into_any());
  *   }
  * ```
  * 
@@ -49,10 +56,13 @@
  * Let's modify our `MainBody` to replace all elements with the attribute `data-replace-with-leptos` with a `MyReplacementComponent`:
  * 
  * ```
- *  fn replace(e:&Element) -> Option<AnyView<Dom>> {
+ * # use leptos::prelude::*;
+ * # use leptos_dyn_dom::*;
+ * # use leptos::web_sys::Element;
+ *  fn replace(e:&Element) -> Option<impl FnOnce() -> AnyView> {
  *    e.get_attribute("data-replace-with-leptos").map(|_| {
- *      let orig = e.clone().into();
- *      view!(<MyReplacementComponent orig/>).into_any()
+ *      let orig:OriginalNode = e.clone().into();
+ *      || view!(<MyReplacementComponent orig/>).into_any()
  *    })
  *  }
  * 
@@ -83,6 +93,10 @@
  * However, it might occasionally be the case that we want to dynamically *extend* the DOM at some point by retrieving HTML from elsewhere, and then want to do a similar "hydration" iteration over the freshly inserted nodes. This is what [`DomStringCont`] is for, and it does not require the `csr` feature:
  * 
  * ```
+ * # use leptos::prelude::*;
+ * # use leptos_dyn_dom::*;
+ * # use leptos::web_sys::Element;
+ * # fn replace(e:&Element) -> Option<fn() -> AnyView> { None }
  *  #[component]
  *  fn MyComponentThatGetsAStringFromSomewhere() -> impl IntoView {
  *   // get some HTML string from somewhere
@@ -97,69 +111,315 @@
  * See the `examples/ssr` directory for a full example.
 */
 
+// Emits a hydration event to the console if the `tracing` feature is enabled; compiles to nothing otherwise.
+macro_rules! trace {
+  ($($arg:tt)*) => {
+    #[cfg(feature="tracing")]
+    { leptos::logging::log!("[leptos-dyn-dom] {}",format_args!($($arg)*)); }
+  }
+}
+
 mod node;
 mod dom;
+mod rules;
+mod view;
+mod progress;
+#[cfg(feature="json")]
+mod json;
+#[cfg(any(feature="csr",feature="hydrate"))]
+mod observer;
+#[cfg(feature="csr")]
+pub mod local;
+#[cfg(all(feature="test-util",any(feature="csr",feature="hydrate")))]
+pub mod test_util;
 
 pub use node::{OriginalNode,AnyTag};
+pub use dom::{HydrateError,HydrateOpts,MountedRoots,HydrateMatch};
+pub use rules::{HydrationRules,RuleView,Matcher,HydratorId,register_hydrator,register_hydrator_with_priority,unregister_hydrator,registry_replace};
+pub use progress::HydrationProgress;
+pub use view::{with_inherited_attrs,with_inherited_attrs_filtered,inherit_attributes,wrap_original,with_context,suspend_with_original,dataset_to_map,HeadElement,replace_head_elements};
+#[cfg(feature="serde")]
+pub use view::parse_dataset;
+#[cfg(feature="leptos_meta")]
+pub use view::hydrate_head;
+#[cfg(feature="json")]
+pub use json::{JsonNode,ComponentFactory,DomJsonCont};
+
+// used by `hydrate_rules!`
+#[doc(hidden)]
+pub mod __private {
+  pub use leptos::{web_sys::Element,prelude::IntoAny};
+  // fixes the argument types of the handler closures
+  #[inline]
+  pub fn apply<V:leptos::prelude::IntoView+'static>(
+    f:impl FnOnce(&Element,leptos::prelude::AnyView) -> V,e:&Element,children:leptos::prelude::AnyView
+  ) -> leptos::prelude::AnyView {
+    IntoAny::into_any(f(e,children))
+  }
+}
 
 #[cfg(any(feature="csr",feature="hydrate"))]
-pub use dom::hydrate_node;
+pub use dom::{hydrate_node,try_hydrate_node,hydrate_children,try_hydrate_children,hydrate_node_with,try_hydrate_node_with,hydrate_node_mut,try_hydrate_node_mut,hydrate_node_in_document,try_hydrate_node_in_document,hydrate_node_idle,hydrate_node_idle_cancellable,CancelHandle,hydrate_node_selector,try_hydrate_node_selector,hydrate_node_when_defined,hydrate_node_async,serialize_node,dry_run_hydrate};
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub use observer::{observe_and_hydrate,observe_and_hydrate_attributes,HydrationObserver};
+#[cfg(feature="leak-check")]
+pub use dom::live_replacement_count;
 
-use leptos::{web_sys::Element, html::Span, math::Mrow, prelude::*};
+use leptos::{web_sys::Element, html::ElementType, prelude::*};
+#[cfg(any(feature="csr",feature="hydrate"))]
 use send_wrapper::SendWrapper;
 
 /// A component that calls `f` on all children of `orig`
 /// to potentially "hydrate" them further, and reinserts the original
 /// element into the DOM.
+///
+/// Unless `skip_head` is set, `orig` itself is passed to `f` as well, and replaced if `f` returns `Some`
+/// (see [`HydrateOpts::include_root`]).
+///
+/// If `progress` is given, it is set to `(done,total)` during the traversal, where `total` is the number
+/// of elements in the subtree and `done` the number of those processed so far (an element that gets
+/// replaced counts together with all of its descendants). Once finished, `done == total`.
 #[component]
 pub fn DomCont<
     V:IntoView+'static,
     R:FnOnce() -> V,
-    F:Fn(&Element) -> Option<R>+'static+Send
->(orig:OriginalNode,#[prop(optional)] skip_head:bool,cont:F) -> impl IntoView {
+    F:FnMut(&Element) -> Option<R>+'static+Send
+>(
+  orig:OriginalNode,#[prop(optional)] skip_head:bool,cont:F,
+  #[prop(optional)] progress:Option<RwSignal<(usize,usize)>>
+) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,skip_head,cont,progress);
   #[cfg(any(feature="csr",feature="hydrate"))]
-  {orig.as_view(move |e| {
-    if skip_head {
-      dom::hydrate_children(e.clone().into(), &cont);
-    } else  {
-      dom::hydrate_node(e.clone().into(), &cont);
-    }
+  {let mut cont = cont; orig.as_view(move |e| {
+    let opts = HydrateOpts::new().include_root(!skip_head);
+    let subtree_size = |e:&Element| e.get_elements_by_tag_name("*").length() as usize + 1;
+    let total = subtree_size(e) - usize::from(skip_head);
+    if let Some(p) = progress { p.set((0,total)) }
+    dom::try_hydrate_node_opts(e.clone().into(), &mut |e:&Element,_:&[u32]| {
+      let r = cont(e);
+      if let Some(p) = progress {
+        let n = if r.is_some() { subtree_size(e) } else { 1 };
+        p.update(|(done,_)| *done = (*done + n).min(total));
+      }
+      r
+    }, &opts).expect("Error hydrating node");
+    if let Some(p) = progress { p.set((total,total)) }
   })}
 }
 
 
+/// Progressive enhancement "in place": Reinserts the original element `orig` itself (preserving its tag,
+/// attributes and position) as the container, calls `cont` on all of its descendants (but not on `orig`
+/// itself; like [`DomCont`] with `skip_head=true`), and then appends `children` (if any) to it; e.g.:
+/// ```ignore
+/// let replace = |e:&Element| e.has_attribute("data-collapsible").then(|| {
+///   let orig = OriginalNode::from(e.clone());
+///   move || view!(<DomEnhanceCont orig cont=replace_inner><CollapseButton/></DomEnhanceCont>)
+/// });
+/// ```
+/// As the top-level element of the replacement, `orig` keeps the `data-leptos-hydrated` attribute this crate
+/// uses to recognize replacements.
+#[component]
+pub fn DomEnhanceCont<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:FnMut(&Element) -> Option<R>+'static+Send
+>(orig:OriginalNode,cont:F,#[prop(optional)] children:Option<Children>) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,cont,children);
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  {let mut cont = cont; orig.as_view(move |e| {
+    let opts = HydrateOpts::new().include_root(false);
+    dom::try_hydrate_node_opts(e.clone().into(), &mut |e:&Element,_:&[u32]| cont(e), &opts)
+      .expect("Error hydrating node");
+    if let Some(children) = children {
+      let mut state = children().build();
+      state.mount(e, None);
+      let mut state = SendWrapper::new(state);
+      Owner::on_cleanup(move || { state.unmount(); drop(state) });
+    }
+  })}
+}
+
 /// A component that inserts the  children of some [`OriginalNode`] 
 /// and renders them into the DOM.
+///
+/// By default, the children stay attached to `orig` until this component is mounted, and are only moved
+/// then; so `orig` (which may still be referenced elsewhere) keeps its children in the meantime. If `detach`
+/// is set, they are removed from `orig` right away into an owned list instead, so that `orig` is empty
+/// afterwards, and no other view rendering `orig` can end up with (some of) the same nodes.
+///
+/// Server-side, this emits the markup `orig` was constructed from via [`OriginalNode::from_html`] (if any),
+/// enclosed in a pair of marker comments. When hydrating, the nodes between the markers are replaced by the
+/// children of `orig` (i.e. those parsed client-side by [`OriginalNode::from_html`] from the same markup).
 #[component]
-pub fn DomChildren(orig:OriginalNode) -> impl IntoView {
+pub fn DomChildren(orig:OriginalNode,#[prop(optional)] detach:bool) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  {
+    let _ = detach;
+    node::OriginalChildren(orig.html)
+  }
    #[cfg(any(feature="csr",feature="hydrate"))]
    {
-    orig.child_vec().into_iter().map(|c| {
+    let children = orig.child_vec();
+    if detach {
+      while let Some(c) = orig.first_child() {
+        let _ = orig.remove_child(&c);
+      }
+    }
+    node::OriginalChildren(children.into_iter().map(|c| {
       match c {
         leptos::either::Either::Left(c) => leptos::either::Either::Left(c.as_view(|_| ())),
         leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
       }
-    }).collect_view()
+    }).collect_view())
   }
 }
 
-/// A component that takes the [`OriginalChildren`] of some preexistent DOM node and a continuation function `f`, and renders them into the DOM. Additionally, `f` is called on every child of the replaced element, to potentially "hydrate" them further.
+/// A component that takes the [`OriginalNode`] of some preexistent DOM node and a continuation function `f`, and renders them into the DOM. Additionally, `f` is called on every child of the replaced element, to potentially "hydrate" them further.
+///
+/// If `progress` is given, it is provided as context to all descendants and only signals completion
+/// once they are all hydrated; otherwise, the [`HydrationProgress`] in context (if any) is used.
+///
+/// If `on_each` is given, it is called on every visited element (in document order) right before `cont`;
+/// see [`HydrateOpts::on_each`].
+///
+/// If `time_budget_ms` is given, only the children themselves are passed to `cont` right away; their descendants
+/// are hydrated afterwards in slices of at most (roughly) that many milliseconds each, yielding to the browser
+/// in between, so that large trees don't block the main thread. `on_load` (and `progress`) then only signal
+/// completion after the last slice.
+///
+/// Similarly, if `window` is given, at most that many replacements of descendants are built per slice, bounding
+/// the number of views (and hence the memory) created at once on very large trees; it can be combined with
+/// `time_budget_ms`. The replacements are created under the reactive owner of this component, so they are
+/// cleaned up along with it; if it is cleaned up before the last slice, the remaining descendants are never
+/// hydrated.
 #[component]
 pub fn DomChildrenCont<
     V:IntoView+'static,
     R:FnOnce() -> V,
     F:Fn(&Element) -> Option<R>+'static+Send+Clone
+>(
+  orig:OriginalNode,cont:F,#[prop(optional)] progress:Option<HydrationProgress>,#[prop(optional)] on_each:Option<OnEach>,
+  #[prop(optional)] on_load:Option<RwSignal<bool>>,#[prop(optional)] time_budget_ms:Option<f64>,
+  #[prop(optional)] window:Option<usize>
+) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,cont,progress,on_each,on_load,time_budget_ms,window);
+  #[cfg(any(feature="csr",feature="hydrate"))]
+   {
+    if let Some(progress) = progress { provide_context(progress); }
+    let progress = progress.or_else(use_context::<HydrationProgress>);
+    if let Some(progress) = progress { progress.begin(); }
+    let opts = match on_each.clone() {
+      Some(f) => HydrateOpts::new().on_each(move |e| f(e)),
+      None => HydrateOpts::new()
+    };
+    let mut deferred = Vec::new();
+    let ret = orig.child_vec().into_iter().map(|c| 
+      match c {
+        leptos::either::Either::Left(c) => leptos::either::Either::Left({
+          if let Some(f) = on_each.as_ref() { f(&c) }
+          if let Some(r) = cont(&c) {
+            leptos::either::Either::Left(dom::with_new_owner(r))
+          } else if time_budget_ms.is_some() || window.is_some() {
+            deferred.push(leptos::web_sys::Node::from((*c).clone()));
+            leptos::either::Either::Right(c.as_view(|_| ()))
+          } else {
+            let cont = cont.clone();
+            let opts = opts.clone();
+            leptos::either::Either::Right(c.as_view(move |e| dom::hydrate_children_with(e.clone().into(),&cont,&opts)))
+          }
+        }),
+        leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
+      }
+    ).collect_view();
+    let finish = move || {
+      if let Some(progress) = progress { progress.end(); }
+      if let Some(on_load) = on_load { on_load.set(true); }
+    };
+    if time_budget_ms.is_some() || window.is_some() {
+      let budget = time_budget_ms.unwrap_or(f64::INFINITY);
+      let window = window.unwrap_or(usize::MAX);
+      let owner = Owner::current();
+      let cancel = CancelHandle::new();
+      Owner::on_cleanup({
+        let cancel = SendWrapper::new(cancel.clone());
+        move || cancel.cancel()
+      });
+      wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = dom::hydrate_children_budgeted(deferred, &cont, &opts, budget, window, owner, &cancel).await {
+          leptos::logging::error!("Error hydrating node: {err}");
+        }
+        if !cancel.is_cancelled() {
+          finish()
+        } else if let Some(progress) = progress {
+          // `on_load` is not set when cancelled, but the progress should not wait forever
+          progress.end()
+        }
+      });
+    } else {
+      finish()
+    }
+    ret
+  }
+}
+
+/// Like [`DomChildrenCont`], but `cont` additionally gets the child-index path of each element,
+/// relative to `orig` (i.e. the `i`th child of `orig` has path `[i]`, its `j`th child `[i,j]`, etc.).
+/// Indices refer to the child nodes (including text nodes) of the original DOM.
+#[component]
+pub fn DomChildrenContPath<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element,&[u32]) -> Option<R>+'static+Send+Clone
 >(orig:OriginalNode,cont:F) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,cont);
+  #[cfg(any(feature="csr",feature="hydrate"))]
+   {
+    orig.child_vec().into_iter().enumerate().map(|(i,c)| 
+      match c {
+        leptos::either::Either::Left(c) => leptos::either::Either::Left({
+          if let Some(r) = cont(&c,&[i as u32]) {
+            leptos::either::Either::Left(dom::with_new_owner(r))
+          } else {
+            let cont = cont.clone();
+            leptos::either::Either::Right(c.as_view(move |e| dom::hydrate_children_path(e.clone().into(),vec![i as u32],&cont)))
+          }
+        }),
+        leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
+      }
+    ).collect_view()
+  }
+}
+
+/// Like [`DomChildrenCont`], but only descends into the children of elements for which `descend_if`
+/// returns `true`; i.e. `cont` is only called on the children of `orig` and within the subtrees allowed by
+/// `descend_if`. Useful if the interactive parts of a document are confined to a few known containers.
+#[component]
+pub fn DomChildrenContFiltered<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static+Send+Clone,
+    D:Fn(&Element) -> bool+'static+Send+Sync+Clone
+>(orig:OriginalNode,descend_if:D,cont:F) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,descend_if,cont);
   #[cfg(any(feature="csr",feature="hydrate"))]
    {
+    let opts = dom::HydrateOpts::new().descend_if(descend_if.clone());
     orig.child_vec().into_iter().map(|c| 
       match c {
         leptos::either::Either::Left(c) => leptos::either::Either::Left({
           if let Some(r) = cont(&c) {
-            leptos::either::Either::Left(r())
+            leptos::either::Either::Left(dom::with_new_owner(r))
           } else {
-            let cont = cont.clone();
-            leptos::either::Either::Right(c.as_view(move |e| dom::hydrate_children(e.clone().into(),&cont)))
+            let (cont,opts,descend) = (cont.clone(),opts.clone(),descend_if(&c));
+            leptos::either::Either::Right(c.as_view(move |e| if descend {
+              dom::hydrate_children_with(e.clone().into(),&cont,&opts)
+            }))
           }
         }),
         leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
@@ -168,20 +428,203 @@ pub fn DomChildrenCont<
   }
 }
 
+/// Like [`DomChildrenCont`], but whether an element is replaced is decided asynchronously by `decide` (e.g. after
+/// checking whether the user has access to a widget); see [`hydrate_node_async`]. Elements with a pending decision
+/// stay untouched until it resolves.
+#[component]
+pub fn DomChildrenContAsync<
+    V:IntoView+'static,
+    R:FnOnce() -> V + 'static,
+    Fut:std::future::Future<Output = Option<R>> + 'static,
+    F:Fn(&Element) -> Option<Fut>+'static+Send+Clone
+>(orig:OriginalNode,decide:F) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,decide);
+  #[cfg(any(feature="csr",feature="hydrate"))]
+   {
+    orig.child_vec().into_iter().map(|c| 
+      match c {
+        leptos::either::Either::Left(c) => leptos::either::Either::Left({
+          let decide = decide.clone();
+          c.as_view(move |e| { dom::hydrate_node_async(e.clone().into(), &decide); })
+        }),
+        leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
+      }
+    ).collect_view()
+  }
+}
+
+/// Like [`DomChildrenCont`], but `cont` returns the replacement view directly, rather than a function
+/// returning it; e.g.:
+/// ```ignore
+/// fn replace(e:&Element) -> Option<AnyView> {
+///   e.has_attribute("data-replace-with-leptos").then(|| view!(<MyReplacementComponent orig=e.clone().into()/>).into_any())
+/// }
+/// view!(<DomChildrenContView orig cont=replace/>)
+/// ```
+/// Note that the view is then created *before* its own reactive owner is set up, so context provided
+/// while creating it (e.g. in a component's body) is not scoped to the replacement; use [`DomChildrenCont`]
+/// (and e.g. [`with_context`]) if that matters.
+#[component]
+pub fn DomChildrenContView<
+    V:IntoView+'static,
+    F:Fn(&Element) -> Option<V>+'static+Send+Clone
+>(orig:OriginalNode,cont:F) -> impl IntoView {
+  let cont = move |e:&Element| cont(e).map(|v| move || v);
+  view!(<DomChildrenCont orig cont/>)
+}
+
+/// Like [`DomChildrenCont`], but `cont` may replace a single element by several sibling views,
+/// which are inserted in order in place of the original element.
+#[component]
+pub fn DomChildrenContMulti<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<Vec<R>>+'static+Send+Clone
+>(orig:OriginalNode,cont:F) -> impl IntoView {
+  let cont = move |e:&Element| cont(e).map(|rs| move || rs.into_iter().map(|r| r()).collect::<Vec<_>>());
+  view!(<DomChildrenCont orig cont/>)
+}
+
+/// Like [`DomChildrenCont`], but defers calling `cont` on (and below) each child element until it
+/// scrolls into view, using an `IntersectionObserver`. `root_margin` (a CSS margin, e.g. `"200px"`)
+/// allows for hydrating elements shortly *before* they become visible.
+#[component]
+pub fn LazyDomChildrenCont<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static+Send+Clone
+>(orig:OriginalNode,cont:F,#[prop(optional,into)] root_margin:Option<String>) -> impl IntoView {
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  let _ = (orig,cont,root_margin);
+  #[cfg(any(feature="csr",feature="hydrate"))]
+   {
+    let observer = observer::lazy_observer(cont, root_margin);
+    orig.child_vec().into_iter().map(|c| 
+      match c {
+        leptos::either::Either::Left(c) => leptos::either::Either::Left({
+          let observer = observer.clone();
+          c.as_view(move |e| observer.observe(e))
+        }),
+        leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
+      }
+    ).collect_view()
+  }
+}
+
+/// A function transforming an HTML string before it is inserted into the DOM; see [`DomStringCont`].
+pub type Sanitizer = std::sync::Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A function called on every element visited by [`DomChildrenCont`].
+pub type OnEach = std::sync::Arc<dyn Fn(&Element) + Send + Sync>;
+
+/// A function called with the resulting HTML after [`DomStringCont`] has been hydrated.
+pub type OnHydrated = std::sync::Arc<dyn Fn(String) + Send + Sync>;
+
+/// A function called with a diagnostic message if [`DomStringCont`] fails to parse its HTML.
+pub type OnParseError = std::sync::Arc<dyn Fn(String) + Send + Sync>;
+
+/// How [`DomStringCont`] parses its HTML string.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub enum ParseMode {
+  /// Sets the `innerHTML` of the wrapper element, i.e. uses the HTML fragment parser in the context of
+  /// the wrapper. Elements not allowed there (e.g. `<html>`, `<head>`) are silently dropped.
+  #[default]
+  FragmentInnerHtml,
+  /// Parses the string as a full document with a `DOMParser`, using the given MIME type (e.g. `text/html`
+  /// or `application/xhtml+xml`), and inserts (copies of) the children of its `<body>` (or of its root
+  /// element, if there is no body). The content is only inserted client-side.
+  DomParser(String)
+}
+
 /// A component that renders a string of valid HTML, and then calls `f` on all the DOM nodes resulting from that to potentially "hydrate" them further.
+///
+/// The HTML is wrapped in a `<wrapper_tag>` element. If no `wrapper_tag` is given, it is chosen based on
+/// the first tag in `html`, so that e.g. `<tr>`s end up in a `<tbody>` and `<li>`s in a `<ul>` (which
+/// the browser would otherwise drop or reparent); defaulting to `<span>`.
+///
+/// If `run_scripts` is set, `<script>` elements in `html` are executed after mounting (which browsers
+/// otherwise don't do for HTML inserted this way). **Caution:** Only use this with trusted `html`, since it
+/// executes arbitrary code!
+///
+/// If `sanitize` is given, it is applied to `html` before it is inserted into the DOM, e.g. to strip
+/// unsafe elements and attributes from untrusted content.
+///
+/// If `flatten` is set, the wrapper element is removed after hydration, and its children are moved into
+/// its parent instead (so they don't affect layout or CSS selectors). They are removed again when the
+/// component is unmounted. Note that other views can then not be inserted relative to this component
+/// anymore, so it should be the last child of its parent if the latter is reactive.
+///
+/// `parse_mode` allows for parsing full documents (or XHTML) with a `DOMParser` instead of the fragment parser;
+/// see [`ParseMode`].
+///
+/// If `on_hydrated` is given, it is called (client-side) with the inner HTML of the wrapper once hydration
+/// is finished (see also [`serialize_node`]), e.g. to compare the result against an expected snapshot.
+///
+/// If `server_transform` is given, it is applied to `html` (after `sanitize`) with the `ssr` feature only, i.e.
+/// before the markup is emitted by the server; e.g. to statically expand parts of the content with an HTML
+/// parser, reducing the work of `cont` on the client. When hydrating, the client keeps the server's markup
+/// and runs `cont` on it.
+///
+/// If `on_parse_error` is given, the parsed content is validated (client-side) before hydrating: For
+/// [`ParseMode::DomParser`] with an XML MIME type, the parser's `<parsererror>` is detected; otherwise (since
+/// browsers parse any HTML somehow), non-empty `html` that results in no nodes at all counts as an error. On
+/// error, the wrapper is emptied, `cont` is not called, `on_load` is not set, and `on_parse_error` is called
+/// with a diagnostic instead. Without `on_parse_error`, errors are logged and the content is hydrated as is.
+///
+/// If `wait_connected` is set, the content is only processed (and `cont` only called) once the wrapper is
+/// connected to the document, which it may not be when mounted (e.g. in portal/teleport scenarios where the
+/// surrounding view is attached later), checking once per task. If the component is cleaned up before,
+/// the content is never hydrated.
 #[component]
 pub fn DomStringCont<
     V:IntoView+'static,
     R:FnOnce() -> V,
     F:Fn(&Element) -> Option<R>+'static
->(html:String,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>) -> impl IntoView {
-    let rf = NodeRef::<Span>::new();
-    rf.on_load(move |e| {
-        #[cfg(any(feature="csr",feature="hydrate"))]
-        {dom::hydrate_node(e.into(), &cont);}
-        if let Some(on_load) = on_load { on_load.set(true); }
-    });
-    view!(<span node_ref=rf inner_html=html/>)
+>(
+  html:String,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>,
+  #[prop(optional,into)] wrapper_tag:Option<String>,#[prop(optional)] run_scripts:bool,
+  #[prop(optional)] sanitize:Option<Sanitizer>,#[prop(optional)] flatten:bool,
+  #[prop(optional)] parse_mode:ParseMode,#[prop(optional)] on_hydrated:Option<OnHydrated>,
+  #[prop(optional)] server_transform:Option<Sanitizer>,#[prop(optional)] on_parse_error:Option<OnParseError>,
+  #[prop(optional)] wait_connected:bool
+) -> impl IntoView {
+    let html = match sanitize { Some(s) => s(&html), None => html };
+    #[cfg(feature="ssr")]
+    let html = match server_transform { Some(t) => t(&html), None => html };
+    #[cfg(not(feature="ssr"))]
+    let _ = server_transform;
+    let opts = StringContOpts { run_scripts, flatten, mime:None, on_hydrated, on_parse_error, wait_connected };
+    match parse_mode {
+      ParseMode::FragmentInnerHtml => {
+        let tag = wrapper_tag.unwrap_or_else(|| wrapper_for(&html).to_string());
+        dom_string_cont(leptos::html::custom(tag), html, cont, on_load, opts)
+      }
+      ParseMode::DomParser(mime) => {
+        let tag = wrapper_tag.unwrap_or_else(|| "div".to_string());
+        dom_string_cont(leptos::html::custom(tag), html, cont, on_load, StringContOpts { mime:Some(mime), ..opts })
+      }
+    }
+}
+
+/// Like [`DomStringCont`], but with an arbitrary (typed) wrapper element, e.g.
+/// ```ignore
+/// view!(<DomStringContIn wrapper=leptos::html::tbody() html cont/>)
+/// ```
+#[component]
+pub fn DomStringContIn<
+    E:ElementType+'static,
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static
+>(
+  wrapper:leptos::html::HtmlElement<E,(),()>,html:String,cont:F,
+  #[prop(optional)] on_load:Option<RwSignal<bool>>,#[prop(optional)] run_scripts:bool,
+  #[prop(optional)] sanitize:Option<Sanitizer>,#[prop(optional)] flatten:bool
+) -> impl IntoView
+where E::Output:leptos::wasm_bindgen::JsCast+Clone+'static {
+    let html = match sanitize { Some(s) => s(&html), None => html };
+    dom_string_cont(wrapper, html, cont, on_load, StringContOpts { run_scripts, flatten, ..Default::default() })
 }
 
 /// Like [`DomStringCont`], but using `<mrow>` instead of `<span>`.
@@ -190,71 +633,859 @@ pub fn DomStringContMath<
     V:IntoView+'static,
     R:FnOnce() -> V,
     F:Fn(&Element) -> Option<R>+'static+Send
->(html:String,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>) -> impl IntoView {
-    let rf = NodeRef::<Mrow>::new();
+>(html:String,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>,#[prop(optional)] sanitize:Option<Sanitizer>) -> impl IntoView {
+    let html = match sanitize { Some(s) => s(&html), None => html };
+    dom_string_cont(leptos::math::mrow(), html, cont, on_load, StringContOpts::default())
+}
+
+/// Like [`DomStringCont`], but using an SVG `<g>` instead of `<span>`, so that `html` (e.g. a fragment of
+/// `<g>`/`<path>` elements) is parsed in the SVG namespace. Use it within an `<svg>` element.
+///
+/// Note that views replacing SVG elements need to be built from SVG elements (e.g. `view!(<g>...</g>)`
+/// within `leptos::svg`) as well to be rendered.
+#[component]
+pub fn DomStringContSvg<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static+Send
+>(html:String,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>,#[prop(optional)] sanitize:Option<Sanitizer>) -> impl IntoView {
+    let html = match sanitize { Some(s) => s(&html), None => html };
+    dom_string_cont(leptos::svg::g(), html, cont, on_load, StringContOpts::default())
+}
+
+/// Like [`DomStringCont`], but loads the HTML asynchronously from the given `html` resource (e.g. a
+/// server function), rendering `fallback` while loading and `error` if loading fails:
+/// ```ignore
+/// let html = Resource::new(|| (), |_| get_html_from_server());
+/// view!(<DomResourceCont html cont fallback=|| view!(<p>"Loading..."</p>)/>)
+/// ```
+#[component]
+pub fn DomResourceCont<
+    V:IntoView+'static,
+    R:FnOnce() -> V + 'static,
+    F:Fn(&Element) -> Option<R>+'static+Send+Sync+Clone
+>(
+  html:Resource<Result<String,ServerFnError>>,cont:F,
+  #[prop(optional,into)] fallback:ViewFnOnce,#[prop(optional,into)] error:ViewFn,
+  #[prop(optional)] on_load:Option<RwSignal<bool>>
+) -> impl IntoView {
+  view!{<Suspense fallback>{move || {
+    let (cont,error) = (cont.clone(),error.clone());
+    Suspend::new(async move { match html.await {
+      Ok(html) => leptos::either::Either::Left(match on_load {
+        Some(on_load) => view!(<DomStringCont html cont on_load/>).into_any(),
+        None => view!(<DomStringCont html cont/>).into_any()
+      }),
+      Err(_) => leptos::either::Either::Right(error.run())
+    }})
+  }}</Suspense>}
+}
+
+/// Like [`DomStringCont`], but with a reactive `html`: Whenever it changes, the previously hydrated
+/// content is torn down (running all cleanups of the replacement views), and the new HTML is inserted
+/// and hydrated.
+///
+/// If `keyed` is set, replacements of elements with a `data-key` attribute are instead kept alive across
+/// changes, as long as an element with the same key is present in the new HTML (analogous to `<For>`):
+/// The existing replacement is then moved to the position of the new element (whose content is ignored,
+/// so the key should change whenever the content does); replacements whose keys are gone are cleaned up,
+/// and new keys are replaced freshly. Keyed elements nested in other keyed elements are left to the
+/// outer element's replacement. Keys should be unique; only the first element with a given key is kept
+/// alive, later ones are replaced anew on every change.
+#[component]
+pub fn DomStringContReactive<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static
+>(
+  #[prop(into)] html:Signal<String>,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>,
+  #[prop(optional,into)] wrapper_tag:Option<String>,#[prop(optional)] keyed:bool
+) -> impl IntoView {
+    let initial = html.get_untracked();
+    let tag = wrapper_tag.unwrap_or_else(|| wrapper_for(&initial).to_string());
+    let rf = NodeRef::<leptos::html::Custom<String>>::new();
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    let _ = (cont,on_load,keyed);
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      // the markup rendered initially, which is hydrated as is on the first run
+      let mut rendered = Some(initial.clone());
+      let instances = std::rc::Rc::new(std::cell::RefCell::new(dom::KeyedInstances::new()));
+      Owner::on_cleanup({
+        let instances = SendWrapper::new(instances.clone());
+        move || instances.borrow_mut().clear()
+      });
+      Effect::new(move |prev:Option<Option<Owner>>| {
+        use leptos::wasm_bindgen::JsCast;
+        let html = html.get();
+        let e : Element = rf.get()?.unchecked_into();
+        // the old replacements need to be cleaned up *before* the new content is inserted
+        if keyed { instances.borrow().detach() }
+        if let Some(Some(owner)) = prev { owner.cleanup() }
+        if rendered.take().as_ref() != Some(&html) { e.set_inner_html(&html) }
+        if keyed {
+          if let Err(err) = instances.borrow_mut().hydrate(&e, &cont) {
+            leptos::logging::error!("Error hydrating keyed elements: {err}");
+          }
+        }
+        let owner = Owner::new();
+        owner.with(|| { dom::hydrate_children(e.into(), &cont); });
+        if let Some(on_load) = on_load { on_load.set(true); }
+        Some(owner)
+      });
+    }
+    leptos::html::custom(tag).node_ref(rf).add_any_attr(leptos::html::inner_html(initial))
+}
+
+/// A component that renders HTML arriving in chunks (e.g. from a streamed server response), hydrating it
+/// incrementally: Whenever a chunk completes one or more top-level elements, they are appended to a
+/// `<wrapper_tag>` element (default: `<div>`) and `cont` is called on them, so widgets light up as soon as their
+/// markup has arrived rather than once the whole document has.
+/// ```ignore
+/// let chunks = fetch_html_stream(url); // impl Stream<Item=String>
+/// view!(<StreamingDomCont chunks cont=replace/>)
+/// ```
+/// Elements split across chunks are only inserted once their end tag has arrived; trailing text is held back
+/// until the next element (or the end of the stream). Since elements are matched by their end tags, content
+/// following an element with an omitted end tag (e.g. `<p>` without `</p>`) is only inserted at the end of the
+/// stream. `on_load` is set once the stream has ended and its remaining content has been hydrated.
+///
+/// The stream is only consumed client-side, once the component is mounted; it stops being consumed once the
+/// wrapper is removed from the document.
+#[component]
+pub fn StreamingDomCont<
+    S:futures::Stream<Item=String>+'static,
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static
+>(
+  chunks:S,cont:F,#[prop(optional)] on_load:Option<RwSignal<bool>>,
+  #[prop(optional,into)] wrapper_tag:Option<String>
+) -> impl IntoView {
+    let tag = wrapper_tag.unwrap_or_else(|| "div".to_string());
+    let rf = NodeRef::<leptos::html::Custom<String>>::new();
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      let owner = Owner::current();
+      let progress = use_context::<HydrationProgress>();
+      if let Some(progress) = progress { progress.begin(); }
+      rf.on_load(move |e| {
+        use leptos::wasm_bindgen::JsCast;
+        use futures::StreamExt;
+        let e : Element = e.unchecked_into();
+        let append = move |html:&str| {
+          let hydrate = || dom::append_and_hydrate(&e, html, &cont);
+          if let Err(err) = owner.as_ref().map_or_else(hydrate,|o| o.with(hydrate)) {
+            leptos::logging::error!("Error hydrating streamed HTML: {err}");
+          }
+          e.is_connected()
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+          let mut chunks = std::pin::pin!(chunks);
+          let mut pending = String::new();
+          let mut connected = true;
+          while connected {
+            let Some(chunk) = chunks.next().await else { break };
+            pending.push_str(&chunk);
+            let len = dom::complete_prefix(&pending);
+            if len == 0 { continue }
+            let rest = pending.split_off(len);
+            connected = append(&pending);
+            pending = rest;
+          }
+          if connected && !pending.is_empty() { append(&pending); }
+          if let Some(progress) = progress { progress.end(); }
+          if let Some(on_load) = on_load { on_load.set(true); }
+        });
+      });
+    }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    let _ = (chunks,cont,on_load);
+    leptos::html::custom(tag).node_ref(rf)
+}
+
+// The optional behaviours of the `DomStringCont` family.
+#[derive(Default)]
+struct StringContOpts {
+  run_scripts:bool,
+  flatten:bool,
+  // if given, `html` is parsed via a `DOMParser` with that MIME type (client-side) rather than
+  // inserted as `innerHTML`.
+  mime:Option<String>,
+  on_hydrated:Option<OnHydrated>,
+  on_parse_error:Option<OnParseError>,
+  // if set, `cont` only runs once the wrapper is connected to the document
+  wait_connected:bool
+}
+
+fn dom_string_cont<
+    E:ElementType+'static,
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static
+>(
+  wrapper:leptos::html::HtmlElement<E,(),()>,html:String,cont:F,on_load:Option<RwSignal<bool>>,
+  opts:StringContOpts
+) -> impl IntoView
+where E::Output:leptos::wasm_bindgen::JsCast+Clone+'static {
+    let StringContOpts { run_scripts, flatten, mime, on_hydrated, on_parse_error, wait_connected } = opts;
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    let nonempty = !html.trim().is_empty();
+    let (html,parse) = match mime {
+      Some(mime) => (String::new(),Some((html,mime))),
+      None => (html,None)
+    };
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    let _ = (cont,parse,run_scripts,flatten,on_hydrated,on_parse_error,wait_connected);
+    let rf = NodeRef::<E>::new();
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    let owner = Owner::current();
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    let progress = use_context::<HydrationProgress>();
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    if let Some(progress) = progress { progress.begin(); }
     rf.on_load(move |e| {
         #[cfg(any(feature="csr",feature="hydrate"))]
-        {dom::hydrate_node(e.into(), &cont);}
-        if let Some(on_load) = on_load { on_load.set(true); }
+        {
+          use leptos::wasm_bindgen::JsCast;
+          let e : Element = e.unchecked_into();
+          let el = e.clone();
+          let own = owner.clone();
+          let run = move || {
+            let error = match parse {
+              Some((html,mime)) => dom::parse_into(&e, &html, &mime).err().map(|err| err.to_string()),
+              None if on_parse_error.is_some() && nonempty && e.first_child().is_none() =>
+                Some("non-empty HTML resulted in no nodes".to_string()),
+              None => None
+            };
+            match (error,on_parse_error) {
+              (Some(err),Some(f)) => {
+                e.set_inner_html("");
+                f(err);
+                if let Some(progress) = progress { progress.end(); }
+                return
+              }
+              (Some(err),None) => leptos::logging::error!("Error parsing HTML: {err}"),
+              _ => ()
+            }
+            if e.namespace_uri().as_deref() == Some(dom::MATHML_NS) {
+              if let Err(err) = dom::fix_mathml_namespace(&e) {
+                leptos::logging::error!("Error parsing MathML: {err}");
+              }
+            }
+            if run_scripts {
+              if let Err(err) = dom::run_scripts(&e) {
+                leptos::logging::error!("Error running scripts: {err}");
+              }
+            }
+            dom::hydrate_node(e.clone().into(), &cont);
+            if let Some(f) = on_hydrated { f(e.inner_html()) }
+            if flatten {
+              let unwrap = || dom::unwrap_element(&e);
+              if let Err(err) = own.as_ref().map_or_else(unwrap,|o| o.with(unwrap)) {
+                leptos::logging::error!("Error flattening: {err}");
+              }
+            }
+            if let Some(progress) = progress { progress.end(); }
+            if let Some(on_load) = on_load { on_load.set(true); }
+          };
+          if !wait_connected || el.is_connected() {
+            run();
+            return
+          }
+          // e.g. rendered into a portal that is only attached later
+          let cancel = CancelHandle::new();
+          if let Some(o) = &owner {
+            let cancel = SendWrapper::new(cancel.clone());
+            o.with(|| Owner::on_cleanup(move || cancel.cancel()));
+          }
+          wasm_bindgen_futures::spawn_local(async move {
+            while !el.is_connected() {
+              dom::yield_to_browser(&cancel,false).await;
+              if cancel.is_cancelled() {
+                if let Some(progress) = progress { progress.end(); }
+                return
+              }
+            }
+            match owner { Some(o) => o.with(run), None => run() }
+          });
+        }
+        #[cfg(not(any(feature="csr",feature="hydrate")))]
+        {
+          let _ = e;
+          if let Some(on_load) = on_load { on_load.set(true); }
+        }
     });
-    view!(<mrow node_ref=rf inner_html=html/>)
+    // `.inner_html(..)` requires the (tachys-private) `ElementWithChildren` bound on `E`
+    wrapper.node_ref(rf).add_any_attr(leptos::html::inner_html(html))
+}
+
+/// The element that can legally contain `html`, judging by its first tag.
+fn wrapper_for(html:&str) -> &'static str {
+  let Some(start) = html.trim_start().strip_prefix('<') else { return "span" };
+  let tag = start.split(|c:char| c.is_whitespace() || c == '>' || c == '/').next().unwrap_or_default();
+  match tag.to_ascii_lowercase().as_str() {
+    "tr" => "tbody",
+    "td" | "th" => "tr",
+    "thead" | "tbody" | "tfoot" | "caption" | "colgroup" => "table",
+    "col" => "colgroup",
+    "li" => "ul",
+    "dt" | "dd" => "dl",
+    "option" | "optgroup" => "select",
+    _ => "span"
+  }
 }
 
 
 // need some check to not iterate over the entire body multiple times for some reason.
 // I'm not sure why this is necessary, but it seems to be.
+// Keyed by the root node, so that independent roots (including shadow roots) can each be hydrated once.
 #[cfg(feature="csr")]
-static DONE : std::sync::OnceLock<()> = std::sync::OnceLock::new();
+thread_local! {
+  static DONE : std::cell::RefCell<Vec<leptos::web_sys::Node>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Resets the guard that makes sure every root (e.g. the `<body>`) is only hydrated once, so that
+/// [`hydrate_body`] and [`hydrate_element`] can be called again - e.g. in tests that set up and tear
+/// down the DOM between cases.
+///
+/// **Caution:** Calling this while a hydrated tree is still mounted and then hydrating the same root
+/// again will hydrate the (already hydrated) DOM a second time.
+#[cfg(feature="csr")]
+pub fn reset_hydration_guard() {
+  DONE.with_borrow_mut(Vec::clear);
+}
 
 /// Hydrates the entire DOM with leptos components, starting at the body.
+///
+/// `v` is a function that takes the [`OriginalNode`] of the `<body>` (likely reinserting them somewhere) and returns some leptos view replacing the original children(!) of the body.
+/// The [`OriginalNode`] is a (detached) `<div>` containing the original children; it is accessible via
+/// [`OriginalNode::as_element`].
+///
+/// The returned [`HydrationHandle`] can be used to tear the hydrated tree down again.
 /// 
-/// `v` is a function that takes the [`OriginalChildren`] of the `<body>` (likely reinserting them somewhere) and returns some leptos view replacing the original children(!) of the body.
+/// Panics if setting up the DOM fails; see [`try_hydrate_body`] for a non-panicking version.
 #[cfg(feature="csr")]
-pub fn hydrate_body<N:IntoView>(
+pub fn hydrate_body<N:IntoView+'static>(
   v:impl FnOnce(OriginalNode) -> N + 'static
-) {
-  // make sure this only ever happens once.
-  if DONE.get().is_some() {return}
-  DONE.get_or_init(|| ());
-  let document = leptos::tachys::dom::document();
-  // We check that the DOM has been fully loaded
-  let state = document.ready_state();
-  let go = move || {
-    let body = leptos::tachys::dom::body();
-    let nd = leptos::tachys::dom::document().create_element("div").expect("Error creating div");
-    while let Some(c) = body.child_nodes().get(0) {
-      nd.append_child(&c).expect("Error appending child");
+) -> HydrationHandle {
+  try_hydrate_body(v).expect("Error hydrating body")
+}
+
+/// Like [`hydrate_body`], but returns an error instead of panicking.
+/// 
+/// If the DOM has not been loaded yet, hydration is deferred until `DOMContentLoaded`; errors occurring
+/// then are logged to the console.
+#[cfg(feature="csr")]
+#[inline]
+pub fn try_hydrate_body<N:IntoView+'static>(
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> Result<HydrationHandle,HydrateError> {
+  try_hydrate_body_with(HydrateBodyOpts::default(), v)
+}
+
+/// Like [`hydrate_body`], but hydrates (the children of) the first element matching the CSS `selector`
+/// instead of the `<body>`, leaving the rest of the document untouched.
+/// 
+/// Every root element is only ever hydrated once.
+#[cfg(feature="csr")]
+pub fn hydrate_element<N:IntoView+'static>(
+  selector:&str,
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> HydrationHandle {
+  try_hydrate_element(selector,v).expect("Error hydrating element")
+}
+
+/// Like [`hydrate_element`], but returns an error instead of panicking (see [`try_hydrate_body`]).
+#[cfg(feature="csr")]
+#[inline]
+pub fn try_hydrate_element<N:IntoView+'static>(
+  selector:&str,
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> Result<HydrationHandle,HydrateError> {
+  try_hydrate_body_with(HydrateBodyOpts::new().root_selector(selector), v)
+}
+
+/// Options for [`hydrate_body_with`].
+#[cfg(feature="csr")]
+#[derive(Clone,Debug,Default)]
+pub struct HydrateBodyOpts {
+  skip_head:bool,
+  hydrated_marker:Option<String>,
+  root_selector:Option<String>,
+  on_complete:Option<OnComplete>,
+  restorable:bool
+}
+
+// shared, so that the options stay `Clone`; the callback is taken by the first root completing
+#[cfg(feature="csr")]
+#[derive(Clone)]
+struct OnComplete(std::rc::Rc<std::cell::Cell<Option<Callback>>>);
+
+#[cfg(feature="csr")]
+type Callback = Box<dyn FnOnce()>;
+
+#[cfg(feature="csr")]
+impl std::fmt::Debug for OnComplete {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str("OnComplete") }
+}
+
+#[cfg(feature="csr")]
+impl HydrateBodyOpts {
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Leaves elements that belong into the `<head>` (`<script>`, `<style>`, `<link>`, `<meta>`, `<title>`,
+  /// `<base>`), but have been moved into the root by the browser, in place, rather than passing them
+  /// on to the view (like [`DomCont`]'s `skip_head`, for the `<head>`'s contents).
+  #[inline]
+  pub fn skip_head(mut self,skip:bool) -> Self {
+    self.skip_head = skip;
+    self
+  }
+
+  /// Sets the attribute `marker` on the root once it is hydrated, and skips hydration if it is already
+  /// present - e.g. if several independently compiled scripts on the same page might hydrate the same root
+  /// (which the in-memory guard can not detect). The attribute is removed again when unmounting.
+  #[inline]
+  pub fn hydrated_marker(mut self,marker:impl Into<String>) -> Self {
+    self.hydrated_marker = Some(marker.into());
+    self
+  }
+
+  /// Hydrates (the children of) the first element matching the CSS `selector` instead of the `<body>`
+  /// (see [`hydrate_element`]).
+  #[inline]
+  pub fn root_selector(mut self,selector:impl Into<String>) -> Self {
+    self.root_selector = Some(selector.into());
+    self
+  }
+
+  /// Calls `f` once the initial hydration of the root has finished, i.e. after the view has been mounted and
+  /// all hydration tasks registered with its [`HydrationProgress`] are done; e.g. to signal readiness to the
+  /// host page of an embedded script:
+  /// ```ignore
+  /// hydrate_body_with(HydrateBodyOpts::new().on_complete(|| {
+  ///   let _ = window().dispatch_event(&web_sys::CustomEvent::new("my-widget-ready").unwrap());
+  /// }), |orig| view!(<DomChildrenCont orig cont=replace/>));
+  /// ```
+  /// `f` is not called if the root is not hydrated (e.g. because it already has been), or unmounted first.
+  #[inline]
+  pub fn on_complete(mut self,f:impl FnOnce() + 'static) -> Self {
+    self.on_complete = Some(OnComplete(std::rc::Rc::new(std::cell::Cell::new(Some(Box::new(f))))));
+    self
+  }
+
+  /// Keeps a (deep) copy of the root's original children, so that
+  /// [`unmount_and_restore`](HydrationHandle::unmount_and_restore) can re-attach them. Off by default,
+  /// since copying e.g. the whole `<body>` is expensive on large pages.
+  #[inline]
+  pub fn restorable(mut self,restorable:bool) -> Self {
+    self.restorable = restorable;
+    self
+  }
+}
+
+/// Like [`hydrate_body`], but configured by `opts`.
+#[cfg(feature="csr")]
+pub fn hydrate_body_with<N:IntoView+'static>(
+  opts:HydrateBodyOpts,
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> HydrationHandle {
+  try_hydrate_body_with(opts,v).expect("Error hydrating body")
+}
+
+/// Like [`hydrate_body_with`], but returns an error instead of panicking (see [`try_hydrate_body`]).
+#[cfg(feature="csr")]
+pub fn try_hydrate_body_with<N:IntoView+'static>(
+  opts:HydrateBodyOpts,
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> Result<HydrationHandle,HydrateError> {
+  let handle = HydrationHandle::new();
+  let (state,progress) = (handle.state.clone(),handle.progress);
+  on_dom_loaded(move || {
+    let root = match &opts.root_selector {
+      Some(selector) => leptos::tachys::dom::document().query_selector(selector)?
+        .ok_or_else(|| HydrateError::NotFound(selector.clone()))?,
+      None => leptos::tachys::dom::body().into()
     };
-    mount_to_body(move || v(nd.into()));
+    hydrate_root(root.into(), v, &state, progress, &opts)
+  })?;
+  Ok(handle)
+}
+
+/// Like [`hydrate_element`], but hydrates (the children of) the open shadow root of `host`, e.g. for host
+/// pages that mount their content inside a web component.
+///
+/// Panics if setting up the DOM fails; see [`try_hydrate_shadow`] for a non-panicking version.
+#[cfg(feature="csr")]
+pub fn hydrate_shadow<N:IntoView+'static>(
+  host:&Element,
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> HydrationHandle {
+  try_hydrate_shadow(host,v).expect("Error hydrating shadow root")
+}
+
+/// Like [`hydrate_shadow`], but returns an error instead of panicking (see [`try_hydrate_body`]).
+///
+/// Returns [`HydrateError::NoShadowRoot`] if `host` has no shadow root, or only a closed one
+/// (which is inaccessible from outside).
+#[cfg(feature="csr")]
+pub fn try_hydrate_shadow<N:IntoView+'static>(
+  host:&Element,
+  v:impl FnOnce(OriginalNode) -> N + 'static
+) -> Result<HydrationHandle,HydrateError> {
+  let root = host.shadow_root().ok_or(HydrateError::NoShadowRoot)?;
+  let handle = HydrationHandle::new();
+  let (state,progress) = (handle.state.clone(),handle.progress);
+  on_dom_loaded(move || hydrate_root(root.into(), v, &state, progress, &HydrateBodyOpts::default()))?;
+  Ok(handle)
+}
+
+/// A handle to a tree mounted by [`hydrate_body`] or [`hydrate_element`].
+///
+/// Dropping the handle leaves the tree mounted; use [`unmount`](HydrationHandle::unmount) or
+/// [`unmount_and_restore`](HydrationHandle::unmount_and_restore) to remove it.
+#[cfg(feature="csr")]
+pub struct HydrationHandle {
+  state:std::rc::Rc<std::cell::RefCell<HandleState>>,
+  progress:HydrationProgress
+}
+
+#[cfg(feature="csr")]
+#[derive(Default)]
+enum HandleState {
+  #[default]
+  Pending,
+  Mounted {
+    root:leptos::web_sys::Node,
+    original:Option<leptos::web_sys::Node>,
+    unmount:Box<dyn FnOnce()>,
+    marker:Option<String>
+  },
+  Unmounted
+}
+
+#[cfg(feature="csr")]
+impl HydrationHandle {
+  #[inline]
+  fn new() -> Self { Self::with_progress(HydrationProgress::new()) }
+
+  fn with_progress(progress:HydrationProgress) -> Self {
+    Self { state:std::rc::Rc::default(), progress }
+  }
+
+  /// The [`HydrationProgress`] of the hydration pass, which is provided as context to the hydrated tree.
+  #[inline]
+  pub fn progress(&self) -> HydrationProgress { self.progress }
+
+  /// Whether the tree has been mounted yet (hydration may be deferred until the DOM is loaded).
+  #[inline]
+  pub fn is_mounted(&self) -> bool {
+    matches!(*self.state.borrow(),HandleState::Mounted{..})
+  }
+
+  /// Unmounts the hydrated tree and disposes of its reactive owner, leaving the root element empty.
+  /// The root can then be hydrated again.
+  ///
+  /// If hydration has been deferred and not happened yet, it is cancelled.
+  #[inline]
+  pub fn unmount(self) { self.take(); }
+
+  /// Like [`unmount`](HydrationHandle::unmount), but re-attaches (a copy of) the original children
+  /// the root element had before hydration. Requires [`HydrateBodyOpts::restorable`]; otherwise,
+  /// this is the same as [`unmount`](HydrationHandle::unmount).
+  pub fn unmount_and_restore(self) {
+    if let Some((root,Some(original))) = self.take() {
+      while let Some(c) = original.first_child() {
+        let _ = root.append_child(&c);
+      }
+    }
+  }
+
+  fn take(&self) -> Option<(leptos::web_sys::Node,Option<leptos::web_sys::Node>)> {
+    let state = std::mem::replace(&mut *self.state.borrow_mut(),HandleState::Unmounted);
+    let HandleState::Mounted { root, original, unmount, marker } = state else { return None };
+    unmount();
+    if let (Some(marker),Some(e)) = (marker,leptos::wasm_bindgen::JsCast::dyn_ref::<Element>(&root)) {
+      let _ = e.remove_attribute(&marker);
+    }
+    DONE.with_borrow_mut(|d| d.retain(|e| *e != root));
+    Some((root,original))
+  }
+}
+
+#[cfg(feature="csr")]
+impl Drop for HydrationHandle {
+  fn drop(&mut self) {
+    // keep the tree mounted (and the `UnmountHandle` in the state alive) forever - or the state until the
+    // tree is mounted, if hydration has been deferred; unless it has been unmounted already
+    if !matches!(*self.state.borrow(),HandleState::Unmounted) {
+      std::mem::forget(self.state.clone());
+    }
+  }
+}
+
+/// Hydrates several disconnected root elements (e.g. a header, a sidebar and a footer widget) under a
+/// single shared reactive owner, so that context only needs to be provided once for all of them:
+/// ```ignore
+/// let handle = MultiRoot::new()
+///   .provide(Theme::default())
+///   .add(header, |orig| view!(<Header orig/>))
+///   .add(sidebar, |orig| view!(<Sidebar orig/>))
+///   .hydrate_all();
+/// ```
+/// Every region behaves like one hydrated by [`hydrate_element`]; they share one [`HydrationProgress`].
+#[cfg(feature="csr")]
+#[derive(Default)]
+pub struct MultiRoot {
+  contexts:Vec<Box<dyn FnOnce()>>,
+  roots:Vec<(Element,RootView)>,
+  restorable:bool
+}
+
+#[cfg(feature="csr")]
+type RootView = Box<dyn FnOnce(OriginalNode) -> AnyView>;
+
+#[cfg(feature="csr")]
+impl MultiRoot {
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Provides `value` as context to all regions.
+  pub fn provide<T:Send+Sync+'static>(mut self,value:T) -> Self {
+    self.contexts.push(Box::new(move || provide_context(value)));
+    self
+  }
+
+  /// Registers the region `root`, whose children are replaced by the view returned by `v`
+  /// (as in [`hydrate_element`]).
+  pub fn add<N:IntoView+'static>(mut self,root:Element,v:impl FnOnce(OriginalNode) -> N + 'static) -> Self {
+    self.roots.push((root,Box::new(move |orig| v(orig).into_any())));
+    self
+  }
+
+  /// Keeps copies of the regions' original children for
+  /// [`MultiRootHandle::unmount_and_restore`] (see [`HydrateBodyOpts::restorable`]).
+  #[inline]
+  pub fn restorable(mut self,restorable:bool) -> Self {
+    self.restorable = restorable;
+    self
+  }
+
+  /// Hydrates all registered regions.
+  ///
+  /// Panics if setting up the DOM fails; see [`try_hydrate_all`](MultiRoot::try_hydrate_all) for a
+  /// non-panicking version.
+  pub fn hydrate_all(self) -> MultiRootHandle {
+    self.try_hydrate_all().expect("Error hydrating roots")
+  }
+
+  /// Like [`hydrate_all`](MultiRoot::hydrate_all), but returns an error instead of panicking. Regions
+  /// hydrated before the error occurred are unmounted again.
+  pub fn try_hydrate_all(self) -> Result<MultiRootHandle,HydrateError> {
+    let owner = Owner::new();
+    let progress = HydrationProgress::new();
+    let mut handle = MultiRootHandle { owner:owner.clone(), handles:Vec::new(), progress };
+    let opts = HydrateBodyOpts::new().restorable(self.restorable);
+    let res = owner.with(|| {
+      provide_context(progress);
+      for c in self.contexts { c() }
+      for (root,v) in self.roots {
+        let h = HydrationHandle::with_progress(progress);
+        hydrate_root(root.into(), v, &h.state, progress, &opts)?;
+        handle.handles.push(h);
+      }
+      Ok(())
+    });
+    match res {
+      Ok(()) => Ok(handle),
+      Err(e) => { handle.unmount(); Err(e) }
+    }
+  }
+}
+
+/// A handle to the regions hydrated by [`MultiRoot::hydrate_all`].
+///
+/// Like [`HydrationHandle`], dropping it leaves the regions mounted.
+#[cfg(feature="csr")]
+#[derive(Default)]
+pub struct MultiRootHandle {
+  owner:Owner,
+  handles:Vec<HydrationHandle>,
+  progress:HydrationProgress
+}
+
+#[cfg(feature="csr")]
+impl MultiRootHandle {
+  /// The [`HydrationProgress`] shared by all regions.
+  #[inline]
+  pub fn progress(&self) -> HydrationProgress { self.progress }
+
+  /// Unmounts all regions and disposes of the shared reactive owner.
+  pub fn unmount(mut self) {
+    for h in std::mem::take(&mut self.handles) { h.unmount() }
+    self.owner.cleanup();
+  }
+
+  /// Like [`unmount`](MultiRootHandle::unmount), but re-attaches (copies of) the original children
+  /// of all regions. Requires [`MultiRoot::restorable`].
+  pub fn unmount_and_restore(mut self) {
+    for h in std::mem::take(&mut self.handles) { h.unmount_and_restore() }
+    self.owner.cleanup();
+  }
+}
+
+#[cfg(feature="csr")]
+impl Drop for MultiRootHandle {
+  fn drop(&mut self) {
+    // the owner is only weakly referenced by the regions' owners, so it needs to be kept alive
+    if !self.handles.is_empty() { std::mem::forget(self.owner.clone()) }
+  }
+}
+
+#[cfg(feature="csr")]
+fn hydrate_root<N:IntoView+'static>(
+  root:leptos::web_sys::Node,
+  v:impl FnOnce(OriginalNode) -> N + 'static,
+  state:&std::cell::RefCell<HandleState>,
+  progress:HydrationProgress,
+  opts:&HydrateBodyOpts
+) -> Result<(),HydrateError> {
+  use leptos::wasm_bindgen::JsCast;
+  // the handle has been unmounted before the DOM was loaded
+  if matches!(*state.borrow(),HandleState::Unmounted) {return Ok(())}
+  // make sure this only ever happens once per root.
+  if DONE.with_borrow(|d| d.contains(&root)) {return Ok(())}
+  let marker = opts.hydrated_marker.clone().filter(|_| root.is_instance_of::<Element>());
+  if let Some(m) = &marker {
+    let e = root.unchecked_ref::<Element>();
+    if e.has_attribute(m) {return Ok(())}
+    e.set_attribute(m, "")?;
+  }
+  DONE.with_borrow_mut(|d| d.push(root.clone()));
+  let nd = leptos::tachys::dom::document().create_element("div")?;
+  let mut current = root.first_child();
+  while let Some(c) = current {
+    current = c.next_sibling();
+    if opts.skip_head && is_head_element(&c) {continue}
+    nd.append_child(&c)?;
   };
+  let original = if opts.restorable { Some(nd.clone_node_with_deep(true)?) } else { None };
+  progress.begin();
+  let on_complete = opts.on_complete.clone();
+  // the mounting logic only uses `Node` methods on the parent, so `root` may also be a `ShadowRoot`
+  let handle = leptos::mount::mount_to(root.clone().unchecked_into(), move || {
+    provide_context(progress);
+    // effects first run after mounting, when `done` has been set (unless tasks are still pending)
+    if let Some(OnComplete(f)) = on_complete {
+      Effect::new(move |_| if progress.done().get() {
+        if let Some(f) = f.take() { f() }
+      });
+    }
+    v(nd.into())
+  });
+  progress.end();
+  *state.borrow_mut() = HandleState::Mounted { root, original, unmount:Box::new(move || drop(handle)), marker };
+  Ok(())
+}
+
+// whether `n` is an element that belongs into the `<head>`
+#[cfg(feature="csr")]
+fn is_head_element(n:&leptos::web_sys::Node) -> bool {
+  use leptos::wasm_bindgen::JsCast;
+  n.dyn_ref::<Element>().is_some_and(|e|
+    matches!(e.tag_name().to_ascii_lowercase().as_str(),"script"|"style"|"link"|"meta"|"title"|"base")
+  )
+}
+
+// Runs `go` once the DOM has been fully loaded
+#[cfg(feature="csr")]
+fn on_dom_loaded(go:impl FnOnce() -> Result<(),HydrateError> + 'static) -> Result<(),HydrateError> {
+  let document = leptos::tachys::dom::document();
+  let state = document.ready_state();
   if state == "complete" || state == "interactive" {
-    go();
+    go()
   } else {
     use leptos::wasm_bindgen::JsCast;
     let fun = std::rc::Rc::new(std::cell::Cell::new(Some(go)));
     let closure = leptos::wasm_bindgen::closure::Closure::wrap(Box::new(move |_:leptos::web_sys::Event| {
       if let Some(f) = fun.take() {
-        f()
+        if let Err(e) = f() {
+          leptos::logging::error!("Error hydrating: {e}");
+        }
       }
     }) as Box<dyn FnMut(_)>);
-     document.add_event_listener_with_callback("DOMContentLoaded", closure.as_ref().unchecked_ref()).unwrap();
+     document.add_event_listener_with_callback("DOMContentLoaded", closure.as_ref().unchecked_ref())?;
      closure.forget();
+     Ok(())
   }
 }
 
 // ------------------------------------------------------------
 
+#[cfg(any(feature="csr",feature="hydrate"))]
+thread_local! {
+  static CLEANUP_SUPPRESSED : std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Runs `f` with the removal of original nodes on cleanup suppressed: Nodes reinserted by this crate (e.g. via
+/// [`DomChildren`]) are normally removed from the DOM when the reactive owner they were inserted under is cleaned
+/// up; within `f`, they are left where they are. Useful for controlled DOM moves, e.g. relocating a hydrated
+/// widget into another container while disposing of the old one.
+///
+/// Suppression ends with `f`, even if `f` panics.
+#[cfg(any(feature="csr",feature="hydrate"))]
+pub fn with_cleanup_suppressed<T>(f:impl FnOnce() -> T) -> T {
+  struct Guard;
+  impl Drop for Guard {
+    fn drop(&mut self) { CLEANUP_SUPPRESSED.with(|c| c.set(c.get() - 1)) }
+  }
+  CLEANUP_SUPPRESSED.with(|c| c.set(c.get() + 1));
+  let _guard = Guard;
+  f()
+}
+
+// Removes `node` from its current parent when the current owner is cleaned up (see `detach_on_cleanup`).
 #[cfg(any(feature="csr",feature="hydrate"))]
 fn cleanup(node:leptos::web_sys::Node) {
-    let c = SendWrapper::new(node);
-    Owner::on_cleanup(move || {
-        //leptos::logging::warn!("Trying to cleanup {}",prettyprint(&*c));
-        if let Some(p) = c.parent_element() {
-        let _ = p.remove_child(&c);
-        } /*else {
-        leptos::logging::warn!("No parent found");
-        }*/
-    });
+    let f = SendWrapper::new(detach_on_cleanup(node));
+    Owner::on_cleanup(move || f.take()());
+}
+
+// Like `cleanup`, but without a `SendWrapper` (see `local`).
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn cleanup_local(node:leptos::web_sys::Node) {
+    dom::on_cleanup_local(detach_on_cleanup(node));
+}
+
+// A callback removing `c` from its current parent - but only if that is still the parent it had when calling this:
+// If the node has been moved in the meantime (e.g. reinserted into another replacement view) or detached
+// (e.g. by a cleanup during nested teardown), it is left alone; this also avoids the `NotFoundError`
+// thrown by `remove_child` if the relationship has changed. Nodes that are detached when calling this (e.g.
+// the element of a `DomCont` built before being mounted) are removed from whatever parent they have been
+// mounted into since.
+#[cfg(any(feature="csr",feature="hydrate"))]
+fn detach_on_cleanup(c:leptos::web_sys::Node) -> impl FnOnce() {
+    let parent = c.parent_node();
+    move || {
+        //leptos::logging::warn!("Trying to cleanup {}",prettyprint(&c));
+        if CLEANUP_SUPPRESSED.with(std::cell::Cell::get) > 0 {
+          trace!("cleanup node={} (suppressed)",c.node_name());
+          return
+        }
+        match (c.parent_node(),parent) {
+          (Some(p),None) => {
+            trace!("cleanup node={} parent={} (mounted later)",c.node_name(),p.node_name());
+            let _ = p.remove_child(&c);
+          }
+          (Some(p),Some(recorded)) if p == recorded => {
+            trace!("cleanup node={} parent={}",c.node_name(),p.node_name());
+            let _ = p.remove_child(&c);
+          }
+          _ => { trace!("cleanup node={} (moved or already detached)",c.node_name()); }
+        }
+    }
 }
 /*
 #[cfg(any(feature="csr",feature="hydrate"))]
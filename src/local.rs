@@ -0,0 +1,43 @@
+//! Variants of the hydration functions and components for (single-threaded) client-side rendering, which
+//! don't require the continuations to be `Send` (or `Clone`), e.g. so they can capture `Rc`s or DOM values
+//! directly.
+//!
+//! The state of the replacement views (and the removal of hydrated nodes on cleanup) is kept in a thread-local
+//! map rather than in [`SendWrapper`](send_wrapper::SendWrapper)s, with only the (`Send`) keys passed to
+//! [`Owner::on_cleanup`]. Note that [`OriginalNode`]s themselves still wrap their element.
+
+use leptos::{web_sys::{Element,Node}, prelude::*};
+use crate::{dom, HydrateOpts, OriginalNode};
+
+/// Like [`hydrate_node`](crate::hydrate_node), but without requiring `replace` to be `'static`, and keeping the
+/// state of the replacements as described in the [module documentation](self).
+pub fn hydrate_node_local<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>
+>(node:Node,replace:&F) -> usize {
+  dom::try_hydrate_node_opts(node, &mut |e:&Element,_:&[u32]| replace(e), &HydrateOpts::new().local())
+    .expect("Error hydrating node")
+}
+
+/// Like [`DomChildrenCont`](crate::DomChildrenCont), but without requiring `cont` to be `Send` or `Clone`.
+#[component]
+pub fn DomChildrenContLocal<
+    V:IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static
+>(orig:OriginalNode,cont:F) -> impl IntoView {
+  let opts = HydrateOpts::new().local();
+  orig.child_vec().into_iter().map(|c| 
+    match c {
+      leptos::either::Either::Left(c) => leptos::either::Either::Left({
+        if let Some(r) = cont(&c) {
+          leptos::either::Either::Left(dom::with_new_owner(r))
+        } else {
+          leptos::either::Either::Right(c.as_view(|e| dom::hydrate_children_with(e.clone().into(),&cont,&opts)))
+        }
+      }),
+      leptos::either::Either::Right(c) => leptos::either::Either::Right(c)
+    }
+  ).collect_view()
+}
@@ -1,7 +1,10 @@
-use leptos::{prelude::*, web_sys::Element,html::ElementType};
+use leptos::{web_sys::Element,html::ElementType};
 
 
 /// Represents the original children some node in the DOM had, to be used in the [`DomChildren`](super::DomChildren), [`DomChildrenCont`](super::DomChildrenCont) and [`DomStringCont`](super::DomStringCont) components.
+///
+/// The captured children can be inspected (and selectively reused) via [`child_nodes`](OriginalNode::child_nodes)
+/// and [`child_elements`](OriginalNode::child_elements).
 #[cfg(any(feature="csr",feature="hydrate"))]
 #[derive(Clone)]
 pub struct OriginalNode{
@@ -14,13 +17,26 @@ impl std::ops::Deref for OriginalNode {
   fn deref(&self) -> &Self::Target { &self.inner }
 }
 
-  // Server side, this is just an empty struct, since there's no DOM anyway.
+  // Server side, there's no DOM anyway; we only keep the markup of the children, if known (see `from_html`).
 #[cfg(not(any(feature="csr",feature="hydrate")))]
 #[derive(Clone)]
-pub struct OriginalNode {}
+pub struct OriginalNode {
+  pub(crate) html:String
+}
 
+#[cfg(any(feature="csr",feature="hydrate"))]
 pub(crate) struct PlainNode(send_wrapper::SendWrapper<web_sys::Node>);
 
+/// The children rendered by [`DomChildren`](crate::DomChildren): Server-side, the markup of an
+/// [`OriginalNode::from_html`], enclosed in the comments [`CHILDREN_START`] and [`CHILDREN_END`]; when hydrating,
+/// the nodes between those are replaced by the (client-side) children.
+pub(crate) struct OriginalChildren<V>(pub(crate) V);
+
+/// Comment preceding the server-rendered markup of a [`DomChildren`](crate::DomChildren).
+pub(crate) const CHILDREN_START: &str = "leptos-dyn-dom";
+/// Comment following the server-rendered markup of a [`DomChildren`](crate::DomChildren).
+pub(crate) const CHILDREN_END: &str = "/leptos-dyn-dom";
+
 impl<E:Into<Element>> From<E> for OriginalNode {
   #[inline]
   fn from(value: E) -> Self { Self::new(value.into()) }
@@ -36,23 +52,18 @@ impl OriginalNode {
       }
     }
     #[cfg(not(any(feature="csr",feature="hydrate")))]
-    { OriginalNode{} }
+    { OriginalNode{ html:String::new() } }
   }
 
   #[cfg(any(feature="csr",feature="hydrate"))]
   pub(crate) fn child_vec(&self) -> Vec<leptos::either::Either<Self,PlainNode>> {
     use leptos::wasm_bindgen::JsCast;
-    let mut i = 0;
-    let mut ret = Vec::new();
-    while let Some(c) = self.child_nodes().get(i) {
-      i += 1;
-      ret.push(match c.dyn_into::<Element>() {
-        Ok(e) => leptos::either::Either::Left(Self {inner:send_wrapper::SendWrapper::new(e)}),
-        Err(n) => leptos::either::Either::Right(PlainNode(send_wrapper::SendWrapper::new(n)))
-      });
-    }
-    ret
-  } 
+    // `NodeList::item` may be linear in the index, so walk the sibling pointers instead
+    self.child_nodes().map(|c| match c.dyn_into::<Element>() {
+      Ok(e) => leptos::either::Either::Left(Self {inner:send_wrapper::SendWrapper::new(e)}),
+      Err(n) => leptos::either::Either::Right(PlainNode(send_wrapper::SendWrapper::new(n)))
+    }).collect()
+  }
 /*
   #[inline]
   pub fn into_view(self,on_load:Option<RwSignal<bool>>) -> impl IntoView {
@@ -92,19 +103,78 @@ impl OriginalNode {
   }
    */
 
-   #[inline]
-  pub(crate) fn as_view(&self,mut cont:impl FnMut(&mut Element) + 'static + Send) -> impl IntoView {
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  #[inline]
+  pub(crate) fn as_view(&self,cont:impl FnOnce(&mut Element)) -> Self {
+    let mut slf = self.clone();
+    cont(&mut slf.inner);
+    slf
+  }
+
+  /// The underlying (original) element, e.g. the container `<div>` holding the original children of the `<body>`
+  /// in [`hydrate_body`](super::hydrate_body), to set attributes on or observe it (`None` server-side).
+  /// Client-side, an [`OriginalNode`] also dereferences to it.
+  #[inline]
+  pub fn as_element(&self) -> Option<&Element> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { Some(&self.inner) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { None }
+  }
+
+  /// Parses `html` into a detached `<div>` (without mounting anything), whose children are the parsed nodes;
+  /// e.g. to feed into [`DomChildren`](super::DomChildren)/[`DomChildrenCont`](super::DomChildrenCont), or to
+  /// test replacement logic. Like [`DomStringCont`](super::DomStringCont), this uses the HTML fragment parser,
+  /// and `<script>`s are not executed.
+  /// 
+  /// Server-side, the node keeps `html` as is, so that [`DomChildren`](super::DomChildren) emits it
+  /// verbatim - matching what it renders client-side.
+  pub fn from_html(html:&str) -> Self {
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { Self{ html:html.to_string() } }
     #[cfg(any(feature="csr",feature="hydrate"))]
     {
-      let mut slf = self.clone();
-      cont(&mut slf.inner);
-      slf
+      let e = leptos::prelude::document().create_element("div").expect("Failed to create element");
+      e.set_inner_html(html);
+      Self::new(e)
+    }
+  }
+
+  /// Collects the given nodes (e.g. the results of a `query_selector_all`, which need not be siblings) as the
+  /// children of a new detached `<div>`, so that [`DomChildren`](super::DomChildren)/[`DomChildrenCont`](super::DomChildrenCont)
+  /// can render an arbitrary collection of nodes. The nodes are *moved* out of their current positions (in
+  /// the given order); use [`deep_clone`](OriginalNode::deep_clone)s to keep them in place.
+  ///
+  /// Server-side, this returns an empty node.
+  pub fn from_nodes(_nodes:impl IntoIterator<Item = web_sys::Node>) -> Self {
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { Self{ html:String::new() } }
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      let e = leptos::prelude::document().create_element("div").expect("Failed to create element");
+      for n in _nodes {
+        if let Err(err) = e.append_child(&n) {
+          leptos::logging::error!("Error collecting node: {err:?}");
+        }
+      }
+      Self::new(e)
     }
   }
 
+  /// Like [`from_nodes`](OriginalNode::from_nodes), for the nodes in `list`. Since a live `NodeList` (e.g. some
+  /// element's `child_nodes()`) changes while its nodes are moved, the nodes are collected first.
+  pub fn from_node_list(list:&web_sys::NodeList) -> Self {
+    Self::from_nodes((0..list.length()).filter_map(|i| list.item(i)).collect::<Vec<_>>())
+  }
+
+  /// Returns an independent copy of the original element, including all of its descendants.
+  /// 
+  /// Rendering an [`OriginalNode`] moves its children into the new view; so to render the same original
+  /// content in several places (e.g. a preview and a detail pane), render a `deep_clone` in all but one.
+  /// Note that event listeners attached to the original nodes are not copied.
   pub fn deep_clone(&self) -> Self {
     #[cfg(not(any(feature="csr",feature="hydrate")))]
-    { Self{} }
+    { self.clone() }
     #[cfg(any(feature="csr",feature="hydrate"))]
     {
       use leptos::wasm_bindgen::JsCast;
@@ -114,6 +184,155 @@ impl OriginalNode {
     }
   }
 
+  /// Renders the children of the original element (moving the original nodes into the view), and calls `cont`
+  /// on each child element and - if it is not replaced - its descendants, to potentially replace them; i.e.
+  /// exactly what [`DomChildrenCont`](super::DomChildrenCont) does, but usable as a plain expression within
+  /// any component or view:
+  /// ```ignore
+  /// let replace = |e:&Element| (e.tag_name() == "SECTION").then(|| {
+  ///   let orig = OriginalNode::from(e.clone());
+  ///   move || view!(<Collapsible>{orig.children_into_view_cont(replace_inner, None)}</Collapsible>)
+  /// });
+  /// ```
+  /// Every replacement is created under its own reactive owner. If given, `on_load` is set to `true` once all
+  /// children have been hydrated. Server-side, this renders nothing.
+  pub fn children_into_view_cont<
+    V:leptos::prelude::IntoView+'static,
+    R:FnOnce() -> V,
+    F:Fn(&Element) -> Option<R>+'static+Send+Clone
+  >(self,cont:F,on_load:Option<leptos::prelude::RwSignal<bool>>) -> impl leptos::prelude::IntoView {
+    leptos::prelude::untrack(|| crate::DomChildrenCont(crate::DomChildrenContProps {
+      orig:self,cont,on_load,progress:None,on_each:None,time_budget_ms:None,window:None
+    }))
+  }
+
+  /// The child nodes of the original element, including text and comment nodes
+  /// (empty server-side).
+  pub fn child_nodes(&self) -> impl Iterator<Item = web_sys::Node> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { std::iter::successors(self.inner.first_child(),|c| c.next_sibling()) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { std::iter::empty() }
+  }
+
+  /// The element children of the original element, skipping text and comment nodes
+  /// (empty server-side).
+  pub fn child_elements(&self) -> impl Iterator<Item = OriginalNode> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      std::iter::successors(self.inner.first_element_child(),|c| c.next_element_sibling())
+        .map(Self::new)
+    }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { std::iter::empty() }
+  }
+
+  /// The first descendant of the original element matching the CSS `selector` (`None` server-side,
+  /// or if the selector is invalid).
+  pub fn query_selector(&self,selector:&str) -> Option<OriginalNode> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { self.inner.query_selector(selector).ok().flatten().map(Self::new) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { let _ = selector; None }
+  }
+
+  /// All descendants of the original element matching the CSS `selector`, in document order (empty
+  /// server-side, or if the selector is invalid).
+  pub fn query_selector_all(&self,selector:&str) -> Vec<OriginalNode> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      use leptos::wasm_bindgen::JsCast;
+      let Ok(list) = self.inner.query_selector_all(selector) else { return Vec::new() };
+      (0..list.length()).filter_map(|i| list.get(i)?.dyn_into::<Element>().ok())
+        .map(Self::new).collect()
+    }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { let _ = selector; Vec::new() }
+  }
+
+  /// Whether `self` and `other` refer to the very same DOM element (`false` server-side).
+  #[inline]
+  pub fn same_node(&self,_other:&OriginalNode) -> bool {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { self.inner.is_same_node(Some(&_other.inner)) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { false }
+  }
+
+  /// An id of the underlying DOM element that is unique (within the page) and stable for the element's
+  /// lifetime, e.g. as a key for memoization (`None` server-side). It is assigned on first access, and
+  /// stored in a (non-attribute) property of the element, so it does not show up in the markup.
+  pub fn node_id(&self) -> Option<u64> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    {
+      use leptos::web_sys::js_sys::Reflect;
+      static NEXT_ID : std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+      let key = leptos::wasm_bindgen::JsValue::from_str("__leptosDynDomId");
+      if let Some(id) = Reflect::get(&self.inner, &key).ok().and_then(|v| v.as_f64()) {
+        return Some(id as u64)
+      }
+      let id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+      // ids stay well below 2^53, so they are represented exactly as JS numbers
+      Reflect::set(&self.inner, &key, &(id as f64).into()).ok()?;
+      Some(id)
+    }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { None }
+  }
+
+  /// The tag name of the original element (`None` server-side).
+  #[inline]
+  pub fn tag_name(&self) -> Option<String> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { Some(self.inner.tag_name()) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { None }
+  }
+
+  /// The value of the attribute `name` of the original element, if present (`None` server-side).
+  #[inline]
+  pub fn get_attribute(&self,name:&str) -> Option<String> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { self.inner.get_attribute(name) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { let _ = name; None }
+  }
+
+  /// Rewrites the attributes of the original element in place (e.g. before reinserting it): `f` is called with
+  /// the name and value of every attribute, and returns its new value, or `None` to remove it; e.g.
+  /// ```ignore
+  /// orig.map_attributes(|name,value| match name {
+  ///   "onclick" => None,
+  ///   _ => Some(value.to_string())
+  /// });
+  /// ```
+  /// To rename an attribute (e.g. `data-href` to `href`), set the new one on the element and remove the old one.
+  /// Does nothing server-side.
+  pub fn map_attributes(&self,_f:impl Fn(&str,&str) -> Option<String>) {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    for name in self.inner.get_attribute_names().iter() {
+      let Some(name) = name.as_string() else { continue };
+      let Some(value) = self.inner.get_attribute(&name) else { continue };
+      let res = match _f(&name,&value) {
+        Some(new) if new == value => Ok(()),
+        Some(new) => self.inner.set_attribute(&name, &new),
+        None => self.inner.remove_attribute(&name)
+      };
+      if let Err(err) = res {
+        leptos::logging::error!("Error setting attribute {name}: {err:?}");
+      }
+    }
+  }
+
+  /// The text content of the original element (`None` server-side).
+  #[inline]
+  pub fn text_content(&self) -> Option<String> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { self.inner.text_content() }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { None }
+  }
+
   #[inline]
   pub fn inner_html(&self) -> String {
     #[cfg(any(feature="csr",feature="hydrate"))]
@@ -121,6 +340,19 @@ impl OriginalNode {
     #[cfg(not(any(feature="csr",feature="hydrate")))]
     { String::new() }
   }
+  /// The outer HTML of the original element (`None` server-side).
+  #[inline]
+  pub fn outer_html(&self) -> Option<String> {
+    #[cfg(any(feature="csr",feature="hydrate"))]
+    { Some(self.inner.outer_html()) }
+    #[cfg(not(any(feature="csr",feature="hydrate")))]
+    { None }
+  }
+
+  /// Like [`html_string`](OriginalNode::html_string), but consumes the node.
+  #[inline]
+  pub fn into_html_string(self) -> String { self.html_string() }
+
   #[inline]
   pub fn html_string(&self) -> String { 
     #[cfg(any(feature="csr",feature="hydrate"))]
@@ -169,17 +401,15 @@ pub(crate) fn on_mount(mut node:Element,mut then:impl FnMut(&mut Element) + 'sta
 mod leptos_impl {
   use leptos::prelude::*;
   use web_sys::Element;
-  use super::{OriginalNode,PlainNode};
+  use super::{OriginalNode,OriginalChildren,CHILDREN_START,CHILDREN_END};
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  use super::PlainNode;
 
+  #[cfg(any(feature="csr",feature="hydrate"))]
   impl Render for PlainNode {
     type State = web_sys::Node;
     #[inline]
-    fn build(self) -> Self::State {
-      #[cfg(any(feature="csr",feature="hydrate"))]
-      { self.0.take() }
-      #[cfg(not(any(feature="csr",feature="hydrate")))]
-      { unreachable!() }
-    }
+    fn build(self) -> Self::State { self.0.take() }
     #[inline]
     fn rebuild(self, _state: &mut Self::State) {}
   }
@@ -197,6 +427,7 @@ mod leptos_impl {
     fn rebuild(self, _state: &mut Self::State) {}
   }
 
+  #[cfg(any(feature="csr",feature="hydrate"))]
   impl RenderHtml for PlainNode {
     type AsyncOutput = Self;
     const MIN_LENGTH: usize = 0;
@@ -217,10 +448,7 @@ mod leptos_impl {
             _cursor: &leptos::tachys::hydration::Cursor,
             _position: &leptos::tachys::view::PositionState,
         ) -> Self::State {
-        #[cfg(any(feature="csr",feature="hydrate"))]
-        { self.0.take() }
-        #[cfg(not(any(feature="csr",feature="hydrate")))]
-        { unreachable!() }
+        self.0.take()
     }
   }
 
@@ -251,6 +479,7 @@ mod leptos_impl {
     }
   }
 
+  #[cfg(any(feature="csr",feature="hydrate"))]
   impl AddAnyAttr for PlainNode {
     type Output<SomeNewAttr: leptos::attr::Attribute> = Self;
     fn add_any_attr<NewAttr: leptos::attr::Attribute>(
@@ -270,10 +499,152 @@ mod leptos_impl {
       self
     }
   }
+
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  impl Render for OriginalChildren<String> {
+    type State = ();
+    #[inline]
+    fn build(self) -> Self::State {}
+    #[inline]
+    fn rebuild(self, _state: &mut Self::State) {}
+  }
+
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  impl RenderHtml for OriginalChildren<String> {
+    type AsyncOutput = Self;
+    const MIN_LENGTH: usize = CHILDREN_START.len() + CHILDREN_END.len() + 14;
+    fn dry_resolve(&mut self) { }
+    fn resolve(self) -> impl std::future::Future<Output = Self::AsyncOutput> + Send {
+      std::future::ready(self)
+    }
+    fn html_len(&self) -> usize { Self::MIN_LENGTH + self.0.len() }
+    fn to_html_with_buf(
+            self,
+            buf: &mut String,
+            position: &mut leptos::tachys::view::Position,
+            _escape: bool,
+            _mark_branches: bool,
+        ) {
+      buf.push_str("<!--");
+      buf.push_str(CHILDREN_START);
+      buf.push_str("-->");
+      buf.push_str(&self.0);
+      buf.push_str("<!--");
+      buf.push_str(CHILDREN_END);
+      buf.push_str("-->");
+      *position = leptos::tachys::view::Position::NextChild;
+    }
+    fn hydrate<const FROM_SERVER: bool>(
+            self,
+            _cursor: &leptos::tachys::hydration::Cursor,
+            _position: &leptos::tachys::view::PositionState,
+        ) -> Self::State {}
+  }
+
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  impl<V:Render> Render for OriginalChildren<V> {
+    type State = V::State;
+    #[inline]
+    fn build(self) -> Self::State { self.0.build() }
+    #[inline]
+    fn rebuild(self, state: &mut Self::State) { self.0.rebuild(state) }
+  }
+
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  impl<V:RenderHtml> RenderHtml for OriginalChildren<V> {
+    type AsyncOutput = Self;
+    const MIN_LENGTH: usize = 0;
+    fn dry_resolve(&mut self) { }
+    fn resolve(self) -> impl std::future::Future<Output = Self::AsyncOutput> + Send {
+      std::future::ready(self)
+    }
+    fn to_html_with_buf(
+            self,
+            _buf: &mut String,
+            _position: &mut leptos::tachys::view::Position,
+            _escape: bool,
+            _mark_branches: bool,
+        ) {}
+
+    // Takes over the markup rendered server-side: the nodes between the markers are replaced by the children,
+    // so that the original nodes (which may be referenced elsewhere) end up in the DOM, and the cursor continues
+    // after the end marker.
+    fn hydrate<const FROM_SERVER: bool>(
+            self,
+            cursor: &leptos::tachys::hydration::Cursor,
+            position: &leptos::tachys::view::PositionState,
+        ) -> Self::State {
+      use leptos::tachys::view::Position;
+      use leptos::wasm_bindgen::JsCast;
+      let is_marker = |n:&web_sys::Node,m:&str| n.node_type() == web_sys::Node::COMMENT_NODE && n.node_value().as_deref() == Some(m);
+      match position.get() {
+        Position::FirstChild => cursor.child(),
+        Position::Current => (),
+        _ => cursor.sibling()
+      }
+      let start = cursor.current();
+      position.set(Position::NextChild);
+      let mut state = self.0.build();
+      let Some(parent) = start.parent_node() else {
+        leptos::logging::error!("Error hydrating DomChildren: the cursor is at a detached node");
+        return state
+      };
+      let end = is_marker(&start,CHILDREN_START).then(||
+        std::iter::successors(start.next_sibling(),web_sys::Node::next_sibling).find(|n| is_marker(n,CHILDREN_END))
+      ).flatten();
+      if let Some(end) = end {
+        while let Some(n) = start.next_sibling().filter(|n| *n != end) {
+          let _ = parent.remove_child(&n);
+        }
+        state.mount(parent.unchecked_ref(), Some(&end));
+        cursor.set(end);
+      } else {
+        // not rendered by the server (or by an incompatible version); insert the children without claiming
+        // any nodes, so that the next view is hydrated from `start` on
+        leptos::logging::warn!("DomChildren: no server-rendered markup found; inserting the children instead");
+        state.mount(parent.unchecked_ref(), Some(&start));
+        match start.previous_sibling() {
+          Some(prev) => cursor.set(prev),
+          None => { cursor.set(parent); position.set(Position::FirstChild) }
+        }
+      }
+      state
+    }
+  }
+
+  #[cfg(not(any(feature="csr",feature="hydrate")))]
+  impl AddAnyAttr for OriginalChildren<String> {
+    type Output<SomeNewAttr: leptos::attr::Attribute> = Self;
+    fn add_any_attr<NewAttr: leptos::attr::Attribute>(
+            self,
+            _attr: NewAttr,
+        ) -> Self::Output<NewAttr> {
+      self
+    }
+  }
+
+  #[cfg(any(feature="csr",feature="hydrate"))]
+  impl<V:RenderHtml> AddAnyAttr for OriginalChildren<V> {
+    type Output<SomeNewAttr: leptos::attr::Attribute> = Self;
+    fn add_any_attr<NewAttr: leptos::attr::Attribute>(
+            self,
+            _attr: NewAttr,
+        ) -> Self::Output<NewAttr> {
+      self
+    }
+  }
 }
 
 macro_rules! elems {
   ( $( #[$meta:meta] $htag:ident ),* ==M== $($mtag:ident),* ==S== $($stag:ident),* ) => {
+    /// Any known HTML, MathML or SVG tag; e.g. to choose the wrapper element of a [`DomStringCont`](crate::DomStringCont)
+    /// at runtime:
+    /// ```ignore
+    /// let tag = AnyTag::new("tbody").expect("unknown tag");
+    /// view!(<DomStringCont html cont wrapper_tag=tag.tag_name()/>)
+    /// ```
+    /// MathML and SVG elements need to be created in their respective namespace, which `wrapper_tag` does not do;
+    /// use [`DomStringContIn`](crate::DomStringContIn) with a typed element (e.g. `leptos::svg::g()`) for those.
     #[derive(Copy,Clone,Hash,PartialEq, Eq)]
     pub enum AnyTag {
       $( #[$meta] $htag, )*
@@ -281,6 +652,34 @@ macro_rules! elems {
       $( $stag ),*
     }
     impl AnyTag {
+      /// The tag with the (case-insensitive) name `tag_name`, if known.
+      #[inline]
+      pub fn new(tag_name:&str) -> Option<AnyTag> { Self::from(tag_name) }
+
+      /// The name of the tag, as used in the DOM.
+      pub fn tag_name(self) -> &'static str {
+        match self {
+          $( Self::$htag => ::leptos::html::$htag::TAG, )*
+          $( Self::$mtag => ::leptos::tachys::mathml::$mtag::TAG, )*
+          $( Self::$stag => ::leptos::tachys::svg::$stag::TAG ),*
+        }
+      }
+
+      /// The namespace URI of the element, if it is not an HTML element.
+      pub fn namespace(self) -> Option<&'static str> {
+        if self.is_mathml() || self == Self::Math { Some(crate::dom::MATHML_NS) }
+        else if self.is_svg() { Some(crate::dom::SVG_NS) }
+        else { None }
+      }
+
+      #[allow(unreachable_patterns)]
+      pub fn is_svg(self) -> bool {
+        match self {
+          $(Self::$stag)|* => true,
+          _ => false
+        }
+      }
+
       pub fn from(s:&str) -> Option<AnyTag> {
         $( if s.eq_ignore_ascii_case(::leptos::html::$htag::TAG) {Some(Self::$htag)} else )*
         $( if s.eq_ignore_ascii_case(::leptos::tachys::mathml::$mtag::TAG) {Some(Self::$mtag)} else )*
@@ -0,0 +1,176 @@
+use leptos::{prelude::Owner, web_sys::{Element,MutationObserver,MutationObserverInit,MutationRecord}, IntoView};
+use leptos::web_sys::{IntersectionObserver,IntersectionObserverEntry,IntersectionObserverInit};
+use leptos::wasm_bindgen::{JsCast, closure::Closure};
+use leptos::web_sys::js_sys::Array;
+
+type Callback = Closure<dyn FnMut(Array,MutationObserver)>;
+
+/// Handle returned by [`observe_and_hydrate`]; disconnects the underlying `MutationObserver`
+/// when dropped.
+pub struct HydrationObserver {
+  observer:MutationObserver,
+  _callback:Callback,
+  _owner:Owner,
+  root:Element,
+  init:MutationObserverInit,
+  paused:std::cell::Cell<bool>,
+  handle:std::rc::Rc<dyn Fn(Array)>,
+  // hydrates everything below the root that has not been hydrated yet
+  rescan:Box<dyn Fn()>
+}
+
+impl HydrationObserver {
+  /// Stops hydrating mutations, e.g. while the page's own code mutates the DOM in bulk: Mutations that have
+  /// happened so far are hydrated right away (via `takeRecords()`), and the observer is then disconnected until
+  /// [`resume`](HydrationObserver::resume) is called.
+  pub fn pause(&self) {
+    if self.paused.replace(true) { return }
+    let records = self.observer.take_records();
+    self.observer.disconnect();
+    if records.length() > 0 { (self.handle)(records) }
+  }
+
+  /// Whether the observer is currently [paused](HydrationObserver::pause).
+  #[inline]
+  pub fn is_paused(&self) -> bool { self.paused.get() }
+
+  /// Reconnects the observer after [`pause`](HydrationObserver::pause). If `process` is `true`, the mutations
+  /// made in the meantime are hydrated once, all at the same time, by a pass over the whole subtree of the root
+  /// (skipping replacements inserted before); otherwise, they are ignored (e.g. if the bulk update is known to
+  /// contain nothing to hydrate).
+  pub fn resume(&self,process:bool) {
+    if !self.paused.replace(false) { return }
+    if let Err(err) = self.observer.observe_with_options(&self.root, &self.init) {
+      leptos::logging::error!("Error reconnecting MutationObserver: {err:?}");
+    }
+    if process { (self.rescan)() }
+  }
+}
+
+impl Drop for HydrationObserver {
+  fn drop(&mut self) { self.observer.disconnect(); }
+}
+
+/// Installs a `MutationObserver` on `root` that runs [`hydrate_node`](crate::hydrate_node) with `replace`
+/// on every element subtree subsequently added below `root`.
+/// 
+/// Nodes inserted by this crate itself (i.e. replacement views and their descendants) are skipped.
+/// The replacements are owned by the reactive owner current when calling this function
+/// (or by the returned handle, if there is none). Failing replacements (e.g. of nodes that have been detached
+/// again before the observer is notified) are logged to the console and skipped.
+pub fn observe_and_hydrate<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(root:&Element,replace:F) -> HydrationObserver {
+  observe(root, &[], replace)
+}
+
+/// Like [`observe_and_hydrate`], but additionally watches for changes of the given `attributes` on
+/// elements below `root`: If one of them is set on an element (that is not part of a replacement inserted
+/// by this crate), `replace` is run on that element (only).
+/// 
+/// E.g. `observe_and_hydrate_attributes(root, &["data-replace-with-leptos"], replace)` hydrates elements
+/// that get the attribute `data-replace-with-leptos` at some later point.
+pub fn observe_and_hydrate_attributes<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(root:&Element,attributes:&[&str],replace:F) -> HydrationObserver {
+  observe(root, attributes, replace)
+}
+
+fn observe<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(root:&Element,attributes:&[&str],replace:F) -> HydrationObserver {
+  let owner = Owner::new();
+  Owner::on_cleanup({let owner = owner.clone(); move || drop(owner)});
+  let top = root.clone();
+  let own = owner.clone();
+  let replace = std::rc::Rc::new(replace);
+  let rescan = {
+    let (top,own,replace) = (top.clone(),own.clone(),replace.clone());
+    Box::new(move || own.with(|| if let Err(err) = crate::dom::try_hydrate_children(top.clone().into(), &*replace) {
+      leptos::logging::error!("Error hydrating: {err}");
+    }))
+  };
+  let handle : std::rc::Rc<dyn Fn(Array)> = std::rc::Rc::new(move |records:Array| {
+    own.with(|| for r in records.iter() {
+      let r : MutationRecord = r.unchecked_into();
+      if r.type_() == "attributes" {
+        let (Some(e),Some(name)) = (r.target().and_then(|t| t.dyn_into::<Element>().ok()),r.attribute_name()) else { continue };
+        // our own markers never trigger a replacement
+        if name == crate::dom::HYDRATED_MARKER || !e.has_attribute(&name) { continue }
+        if !top.contains(Some(&e)) || e.has_attribute(crate::dom::HYDRATED_MARKER) || crate::dom::is_hydrated(&top,&e) { continue }
+        // a panic would abort the whole module; e.g. `e` may have been detached in the meantime
+        if let Err(err) = crate::dom::replace_single(&e, &*replace) {
+          leptos::logging::error!("Error hydrating {}: {err}",e.tag_name());
+        }
+        continue
+      }
+      let added = r.added_nodes();
+      let mut i = 0;
+      while let Some(n) = added.get(i) {
+        i += 1;
+        let Some(e) = n.dyn_ref::<Element>() else { continue };
+        if !top.contains(Some(e)) || crate::dom::is_hydrated(&top,e) { continue }
+        if let Err(err) = crate::dom::try_hydrate_node(n.clone(), &*replace) {
+          leptos::logging::error!("Error hydrating {}: {err}",n.node_name());
+        }
+      }
+    })
+  });
+  let h = handle.clone();
+  let callback : Callback = Closure::new(move |records:Array,_:MutationObserver| h(records));
+  let observer = MutationObserver::new(callback.as_ref().unchecked_ref()).expect("Error creating MutationObserver");
+  let init = MutationObserverInit::new();
+  init.set_child_list(true);
+  init.set_subtree(true);
+  if !attributes.is_empty() {
+    init.set_attributes(true);
+    init.set_attribute_filter(&attributes.iter().map(|a| leptos::wasm_bindgen::JsValue::from_str(a)).collect::<Array>());
+  }
+  observer.observe_with_options(root, &init).expect("Error initializing MutationObserver");
+  HydrationObserver {
+    observer, _callback:callback, _owner:owner, root:root.clone(), init,
+    paused:std::cell::Cell::new(false), handle, rescan
+  }
+}
+
+/// Creates an `IntersectionObserver` that runs [`hydrate_node`](crate::hydrate_node) with `cont` on
+/// every observed element as soon as it scrolls into view (and then stops observing it).
+/// The observer is disconnected when the current reactive owner is cleaned up.
+pub(crate) fn lazy_observer<
+  V:IntoView+'static,
+  R:FnOnce() -> V,
+  F:Fn(&Element) -> Option<R>+'static
+>(cont:F,root_margin:Option<String>) -> send_wrapper::SendWrapper<IntersectionObserver> {
+  let owner = Owner::new();
+  let own = owner.clone();
+  let callback : Closure<dyn FnMut(Array,IntersectionObserver)> = Closure::new(move |entries:Array,obs:IntersectionObserver| {
+    own.with(|| for e in entries.iter() {
+      let e : IntersectionObserverEntry = e.unchecked_into();
+      if !e.is_intersecting() { continue }
+      let target = e.target();
+      obs.unobserve(&target);
+      if let Err(err) = crate::dom::try_hydrate_node(target.clone().into(), &cont) {
+        leptos::logging::error!("Error hydrating {}: {err}",target.tag_name());
+      }
+    })
+  });
+  let init = IntersectionObserverInit::new();
+  if let Some(m) = root_margin.as_deref() {
+    init.set_root_margin(m);
+  }
+  let observer = IntersectionObserver::new_with_options(callback.as_ref().unchecked_ref(), &init)
+    .expect("Error creating IntersectionObserver");
+  let obs = send_wrapper::SendWrapper::new((observer.clone(),callback));
+  Owner::on_cleanup(move || {
+    obs.0.disconnect();
+    drop(obs);
+    drop(owner);
+  });
+  send_wrapper::SendWrapper::new(observer)
+}
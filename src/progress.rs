@@ -0,0 +1,51 @@
+use leptos::prelude::*;
+
+/// Tracks the progress of a (possibly nested and partially deferred) hydration pass, e.g. to hide a
+/// loading indicator only once the full tree is hydrated.
+///
+/// [`hydrate_body`](crate::hydrate_body) and [`hydrate_element`](crate::hydrate_element) provide one
+/// as context (see [`HydrationHandle::progress`](crate::HydrationHandle::progress)); alternatively, one
+/// can be passed to [`DomChildrenCont`](crate::DomChildrenCont) explicitly. Components of this crate
+/// that hydrate (sub)trees register with the progress in context, so that [`done`](HydrationProgress::done)
+/// only becomes `true` once all of them are finished.
+///
+/// Elements deferred by [`LazyDomChildrenCont`](crate::LazyDomChildrenCont) are not waited for.
+#[derive(Clone,Copy,Debug)]
+pub struct HydrationProgress {
+  pending:RwSignal<usize>,
+  done:RwSignal<bool>
+}
+
+impl Default for HydrationProgress {
+  #[inline]
+  fn default() -> Self { Self::new() }
+}
+
+impl HydrationProgress {
+  #[inline]
+  pub fn new() -> Self {
+    Self { pending:RwSignal::new(0), done:RwSignal::new(false) }
+  }
+
+  /// Becomes `true` once every registered hydration task has finished (and is `false` before
+  /// the first one has started).
+  #[inline]
+  pub fn done(&self) -> ReadSignal<bool> { self.done.read_only() }
+
+  /// The number of hydration tasks currently in progress.
+  #[inline]
+  pub fn pending(&self) -> ReadSignal<usize> { self.pending.read_only() }
+
+  /// Registers a hydration task; every call needs to be matched by a call to
+  /// [`end`](HydrationProgress::end) once the task is finished.
+  pub fn begin(&self) {
+    self.pending.update(|p| *p += 1);
+    if self.done.get_untracked() { self.done.set(false) }
+  }
+
+  /// Marks a hydration task registered via [`begin`](HydrationProgress::begin) as finished.
+  pub fn end(&self) {
+    self.pending.update(|p| *p = p.saturating_sub(1));
+    if self.pending.get_untracked() == 0 { self.done.set(true) }
+  }
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+use leptos::{prelude::*, web_sys::Element};
+
+/// The (deferred) replacement view returned by a compiled [`HydrationRules`] matcher.
+pub type RuleView = Box<dyn FnOnce() -> AnyView>;
+
+type Handler = Arc<dyn Fn(&Element) -> AnyView + Send + Sync>;
+type Fallback = Arc<dyn Fn(&Element) -> Option<RuleView> + Send + Sync>;
+type MatchCallback = Arc<dyn Fn(&Element,&str) + Send + Sync>;
+
+/// Determines which elements a rule applies to.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Matcher {
+  /// Elements having the given attribute.
+  Attr(String),
+  /// Elements with the given tag name (case-insensitive).
+  Tag(String),
+  /// Elements matching the given CSS selector.
+  Selector(String)
+}
+
+impl Matcher {
+  /// Whether `e` matches.
+  pub fn matches(&self,e:&Element) -> bool {
+    match self {
+      Self::Attr(a) => e.has_attribute(a),
+      Self::Tag(t) => e.tag_name().eq_ignore_ascii_case(t),
+      Self::Selector(s) => e.matches(s).unwrap_or(false)
+    }
+  }
+}
+
+impl std::fmt::Display for Matcher {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Attr(a) => write!(f, "attr:{a}"),
+      Self::Tag(t) => write!(f, "tag:{t}"),
+      Self::Selector(s) => write!(f, "selector:{s}")
+    }
+  }
+}
+
+/// A table of rules for replacing elements, tried in registration order (first match wins),
+/// which compiles into a single function usable as the `cont` of e.g. [`DomChildrenCont`](crate::DomChildrenCont):
+/// ```ignore
+/// let cont = HydrationRules::new()
+///   .on_attr("data-popup", |e| view!(<Popup orig=e.clone().into()/>))
+///   .on_tag("my-widget", |_| view!(<Widget/>))
+///   .on_selector(".chart", |e| view!(<Chart orig=e.clone().into()/>))
+///   .build();
+/// view!(<DomChildrenCont orig cont/>)
+/// ```
+#[derive(Clone,Default)]
+pub struct HydrationRules {
+  rules:Vec<(Matcher,Handler)>,
+  fallback:Option<Fallback>,
+  on_match:Option<MatchCallback>
+}
+
+impl HydrationRules {
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Replaces elements having the attribute `name`.
+  #[inline]
+  pub fn on_attr<V:IntoView+'static>(self,name:&str,f:impl Fn(&Element) -> V + Send + Sync + 'static) -> Self {
+    self.rule(Matcher::Attr(name.to_string()), f)
+  }
+
+  /// Replaces elements with the tag name `tag` (case-insensitive).
+  #[inline]
+  pub fn on_tag<V:IntoView+'static>(self,tag:&str,f:impl Fn(&Element) -> V + Send + Sync + 'static) -> Self {
+    self.rule(Matcher::Tag(tag.to_string()), f)
+  }
+
+  /// Replaces elements matching the CSS `selector`.
+  #[inline]
+  pub fn on_selector<V:IntoView+'static>(self,selector:&str,f:impl Fn(&Element) -> V + Send + Sync + 'static) -> Self {
+    self.rule(Matcher::Selector(selector.to_string()), f)
+  }
+
+  /// Called on every element no rule matches; replaces the element iff it returns `Some`.
+  pub fn fallback<
+    V:IntoView+'static,
+    R:FnOnce() -> V + 'static
+  >(mut self,f:impl Fn(&Element) -> Option<R> + Send + Sync + 'static) -> Self {
+    self.fallback = Some(Arc::new(move |e| f(e).map(|r| Box::new(move || r().into_any()) as RuleView)));
+    self
+  }
+
+  /// Calls `f` whenever a rule matches, with the matched element and the name of the rule - `attr:<name>`,
+  /// `tag:<tag>`, `selector:<selector>`, or `fallback` (if the fallback returned `Some`). Useful for debugging
+  /// which rule fired; with the `tracing` feature, matches are logged regardless.
+  #[inline]
+  pub fn with_match_callback(mut self,f:impl Fn(&Element,&str) + Send + Sync + 'static) -> Self {
+    self.on_match = Some(Arc::new(f));
+    self
+  }
+
+  fn matched(&self,e:&Element,rule:&str) {
+    trace!("rule {rule} matched <{}>",e.tag_name());
+    if let Some(f) = &self.on_match { f(e,rule) }
+  }
+
+  fn rule<V:IntoView+'static>(mut self,m:Matcher,f:impl Fn(&Element) -> V + Send + Sync + 'static) -> Self {
+    self.rules.push((m,Arc::new(move |e| f(e).into_any())));
+    self
+  }
+
+  /// Returns the replacement for `e` according to the first matching rule (or the fallback).
+  pub fn replace(&self,e:&Element) -> Option<RuleView> {
+    for (m,f) in &self.rules {
+      if m.matches(e) {
+        self.matched(e, &m.to_string());
+        let (f,e) = (f.clone(),e.clone());
+        return Some(Box::new(move || f(&e)))
+      }
+    }
+    let r = self.fallback.as_ref().and_then(|f| f(e));
+    if r.is_some() { self.matched(e, "fallback") }
+    r
+  }
+
+  /// Compiles the rules into a single replacement function.
+  pub fn build(self) -> impl Fn(&Element) -> Option<RuleView> + Clone + Send + Sync + 'static {
+    let rules = Arc::new(self);
+    move |e:&Element| rules.replace(e)
+  }
+}
+
+/// Identifies a hydrator registered via [`register_hydrator`], e.g. to [`unregister_hydrator`] it again.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub struct HydratorId(u64);
+
+struct Registered {
+  id:HydratorId,
+  priority:i32,
+  matcher:Matcher,
+  handler:Handler
+}
+
+static REGISTRY : std::sync::RwLock<Vec<Registered>> = std::sync::RwLock::new(Vec::new());
+static NEXT_ID : std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Registers `factory` in the global hydrator registry, for the elements matched by `matcher`; see
+/// [`registry_replace`]. This allows for independent modules (or crates) to each register their own widgets,
+/// without a central `replace` function. Equivalent to [`register_hydrator_with_priority`] with priority `0`.
+#[inline]
+pub fn register_hydrator<V:IntoView+'static>(matcher:Matcher,factory:impl Fn(&Element) -> V + Send + Sync + 'static) -> HydratorId {
+  register_hydrator_with_priority(matcher, 0, factory)
+}
+
+/// Like [`register_hydrator`], but hydrators with a higher `priority` are tried first (and among the same
+/// priority, those registered earlier).
+pub fn register_hydrator_with_priority<V:IntoView+'static>(
+  matcher:Matcher,priority:i32,factory:impl Fn(&Element) -> V + Send + Sync + 'static
+) -> HydratorId {
+  let id = HydratorId(NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+  let mut registry = REGISTRY.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+  // stable w.r.t. registration order
+  let idx = registry.partition_point(|r| r.priority >= priority);
+  registry.insert(idx, Registered { id, priority, matcher, handler:Arc::new(move |e| factory(e).into_any()) });
+  id
+}
+
+/// Removes the hydrator `id` from the global registry; returns whether it was registered.
+pub fn unregister_hydrator(id:HydratorId) -> bool {
+  let mut registry = REGISTRY.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+  let len = registry.len();
+  registry.retain(|r| r.id != id);
+  registry.len() != len
+}
+
+/// A replacement function dispatching to the first matching hydrator in the global registry (see
+/// [`register_hydrator`]):
+/// ```ignore
+/// register_hydrator(Matcher::Tag("my-widget".into()), |_| view!(<Widget/>));
+/// view!(<DomChildrenCont orig cont=registry_replace/>)
+/// ```
+pub fn registry_replace(e:&Element) -> Option<RuleView> {
+  let registry = REGISTRY.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+  let r = registry.iter().find(|r| r.matcher.matches(e))?;
+  trace!("hydrator {} matched <{}>",r.matcher,e.tag_name());
+  let (f,e) = (r.handler.clone(),e.clone());
+  Some(Box::new(move || f(&e)))
+}
+
+/// Compiles a list of rules into a replacement function (like [`HydrationRules`], but without any allocation
+/// or dynamic dispatch for the matching), usable as the `cont` of e.g. [`DomChildrenCont`](crate::DomChildrenCont):
+/// ```ignore
+/// let cont = hydrate_rules!{
+///   "[data-popup]" => |e, orig| view!(<Popup title=e.get_attribute("data-popup")>{orig}</Popup>),
+///   tag "my-chart" => |_, _| view!(<Chart/>),
+///   attr "data-tooltip" => |e, orig| view!(<Tooltip text=e.get_attribute("data-tooltip")>{orig}</Tooltip>)
+/// };
+/// view!(<DomChildrenCont orig cont/>)
+/// ```
+/// Keys are CSS selectors (as string literals), or `tag "<name>"` (case-insensitive) or `attr "<name>"`;
+/// rules are tried in order, and the first match wins. Each handler is called with the matched element and
+/// the view of its children - themselves hydrated with the same rules (via
+/// [`OriginalNode::children_into_view_cont`](crate::OriginalNode::children_into_view_cont)) - and returns the
+/// replacement view.
+///
+/// The macro expands to a function item, so handlers can not capture variables from their environment (use
+/// [`HydrationRules`] for that).
+#[macro_export]
+macro_rules! hydrate_rules {
+  (@rules $e:ident;) => {};
+  (@rules $e:ident; tag $t:literal => $f:expr $(, $($rest:tt)*)?) => {
+    if $e.tag_name().eq_ignore_ascii_case($t) { return Some($crate::hydrate_rules!(@view $e, $f)) }
+    $crate::hydrate_rules!(@rules $e; $($($rest)*)?);
+  };
+  (@rules $e:ident; attr $a:literal => $f:expr $(, $($rest:tt)*)?) => {
+    if $e.has_attribute($a) { return Some($crate::hydrate_rules!(@view $e, $f)) }
+    $crate::hydrate_rules!(@rules $e; $($($rest)*)?);
+  };
+  (@rules $e:ident; $s:literal => $f:expr $(, $($rest:tt)*)?) => {
+    if $e.matches($s).unwrap_or(false) { return Some($crate::hydrate_rules!(@view $e, $f)) }
+    $crate::hydrate_rules!(@rules $e; $($($rest)*)?);
+  };
+  (@view $e:ident, $f:expr) => {{
+    let e = $e.clone();
+    Box::new(move || {
+      let children = $crate::__private::IntoAny::into_any(
+        $crate::OriginalNode::from(e.clone()).children_into_view_cont(__hydrate_rules, None)
+      );
+      $crate::__private::apply($f, &e, children)
+    }) as $crate::RuleView
+  }};
+  ($($rules:tt)*) => {{
+    fn __hydrate_rules(e:&$crate::__private::Element) -> Option<$crate::RuleView> {
+      $crate::hydrate_rules!(@rules e; $($rules)*);
+      None
+    }
+    __hydrate_rules
+  }};
+}
@@ -0,0 +1,41 @@
+//! Helpers for testing replacement logic (requires the `test-util` feature).
+//!
+//! The traversal needs a real DOM implementation, so tests need to run in a browser (or a DOM emulation),
+//! e.g. via `wasm-pack test --headless --firefox` with
+//! ```ignore
+//! wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+//!
+//! #[wasm_bindgen_test::wasm_bindgen_test]
+//! fn replaces_marked_elements() {
+//!   let root = fixture(r#"<p>a</p><p data-x>b</p>"#);
+//!   let _owner = Owner::new(); // owns the replacements
+//!   hydrate_node(root.clone().into(), &|e:&Element| e.has_attribute("data-x").then(|| || view!(<b>"x"</b>)));
+//!   assert_hydrated(&root, "<div><p>a</p><b>x</b></div>");
+//! }
+//! ```
+//! Note that `wasm-pack test --node` does not work, since node provides no `document`. See the crate's
+//! `tests` directory for examples.
+
+use leptos::web_sys::{Element,Node};
+
+/// Parses `html` into a new detached `<div>` (see [`OriginalNode::from_html`](crate::OriginalNode::from_html)),
+/// to be used as the root of a hydration pass.
+pub fn fixture(html:&str) -> Element {
+  let e = leptos::prelude::document().create_element("div").expect("Failed to create element");
+  e.set_inner_html(html);
+  e
+}
+
+/// The serialized subtree of `node` (see [`serialize_node`](crate::serialize_node)), without the markers this
+/// crate sets on replacement views, so it can be compared against the expected markup.
+pub fn hydrated_html(node:&Node) -> String {
+  crate::serialize_node(node).replace(&format!(" {}=\"\"",crate::dom::HYDRATED_MARKER), "")
+}
+
+/// Asserts that the subtree of `node` serializes to `expected` (ignoring the markers this crate sets on
+/// replacement views; see [`hydrated_html`]).
+#[track_caller]
+pub fn assert_hydrated(node:&Node,expected:&str) {
+  let actual = hydrated_html(node);
+  assert_eq!(actual, expected, "hydrated DOM does not match the expected markup");
+}
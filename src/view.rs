@@ -0,0 +1,323 @@
+use leptos::{prelude::*, web_sys::{Element,Node}};
+use leptos::tachys::{
+  hydration::Cursor, view::{Position,PositionState,any_view::AnyViewState},
+  ssr::StreamBuilder, html::attribute::Attribute
+};
+use leptos::wasm_bindgen::JsCast;
+
+/// Wraps a view and calls `f` on the first DOM node of the view, once it is mounted
+/// (client side only).
+pub(crate) struct OnMount<F> {
+  inner:AnyView,
+  f:F
+}
+
+impl<F:FnOnce(&Node) + Send + 'static> OnMount<F> {
+  #[inline]
+  pub(crate) fn new(v:impl IntoView + 'static,f:F) -> Self {
+    Self { inner:v.into_any(), f }
+  }
+}
+
+pub(crate) struct OnMountState<F> {
+  inner:AnyViewState,
+  f:Option<F>
+}
+
+impl<F:FnOnce(&Node)> Mountable for OnMountState<F> {
+  #[inline]
+  fn unmount(&mut self) { self.inner.unmount() }
+  fn mount(&mut self,parent:&Element,marker:Option<&Node>) {
+    let before = match marker {
+      Some(m) => m.previous_sibling(),
+      None => parent.last_child()
+    };
+    self.inner.mount(parent, marker);
+    if let Some(f) = self.f.take() {
+      let first = match before {
+        Some(b) => b.next_sibling(),
+        None => parent.first_child()
+      };
+      if let Some(n) = first {
+        if Some(&n) != marker { f(&n) }
+      }
+    }
+  }
+  #[inline]
+  fn insert_before_this(&self,child:&mut dyn Mountable) -> bool {
+    self.inner.insert_before_this(child)
+  }
+}
+
+impl<F:FnOnce(&Node) + Send + 'static> Render for OnMount<F> {
+  type State = OnMountState<F>;
+  #[inline]
+  fn build(self) -> Self::State {
+    OnMountState { inner:self.inner.build(), f:Some(self.f) }
+  }
+  #[inline]
+  fn rebuild(self,state:&mut Self::State) {
+    self.inner.rebuild(&mut state.inner)
+  }
+}
+
+impl<F:FnOnce(&Node) + Send + 'static> AddAnyAttr for OnMount<F> {
+  type Output<SomeNewAttr: Attribute> = Self;
+  fn add_any_attr<NewAttr: Attribute>(self,attr:NewAttr) -> Self::Output<NewAttr> {
+    Self { inner:self.inner.add_any_attr(attr), f:self.f }
+  }
+}
+
+impl<F:FnOnce(&Node) + Send + 'static> RenderHtml for OnMount<F> {
+  type AsyncOutput = Self;
+  const MIN_LENGTH: usize = 0;
+  #[inline]
+  fn dry_resolve(&mut self) { self.inner.dry_resolve() }
+  async fn resolve(self) -> Self::AsyncOutput {
+    Self { inner:self.inner.resolve().await, f:self.f }
+  }
+  #[inline]
+  fn html_len(&self) -> usize { self.inner.html_len() }
+  fn to_html_with_buf(
+    self,
+    buf: &mut String,
+    position: &mut Position,
+    escape: bool,
+    mark_branches: bool,
+  ) {
+    self.inner.to_html_with_buf(buf, position, escape, mark_branches)
+  }
+  fn to_html_async_with_buf<const OUT_OF_ORDER: bool>(
+    self,
+    buf: &mut StreamBuilder,
+    position: &mut Position,
+    escape: bool,
+    mark_branches: bool,
+  ) {
+    self.inner.to_html_async_with_buf::<OUT_OF_ORDER>(buf, position, escape, mark_branches)
+  }
+  // the DOM nodes already exist when hydrating, so `f` is not called.
+  fn hydrate<const FROM_SERVER: bool>(
+    self,
+    cursor: &Cursor,
+    position: &PositionState,
+  ) -> Self::State {
+    OnMountState { inner:self.inner.hydrate::<FROM_SERVER>(cursor, position), f:None }
+  }
+}
+
+/// Returns a replacement (for use in a `replace` function) that provides `value` as context to (exactly)
+/// the view returned by `v`; e.g.:
+/// ```ignore
+/// let replace = |e:&Element| e.get_attribute("data-config").map(|cfg|
+///   with_context(Config::parse(&cfg), || view!(<Widget/>)) // `Widget` can `use_context::<Config>()`
+/// );
+/// ```
+/// This works since replacements are always evaluated under their own reactive owner.
+pub fn with_context<T:Send+Sync+'static,V>(value:T,v:impl FnOnce() -> V) -> impl FnOnce() -> V {
+  move || {
+    provide_context(value);
+    v()
+  }
+}
+
+/// Returns a replacement (for use in a `replace` function) that renders the view resolved by the future
+/// returned by `f` within a `<Suspense>`, using (a copy of) the original element `e` as the fallback; so the
+/// original markup stays visible until the data has loaded:
+/// ```ignore
+/// let replace = |e:&Element| e.has_attribute("data-chart").then(||
+///   suspend_with_original(e, |orig| async move {
+///     let data = load_chart_data().await;
+///     view!(<Chart data orig/>)
+///   })
+/// );
+/// ```
+/// Since the fallback is a deep clone, `f` is free to reinsert `orig` itself into its view.
+pub fn suspend_with_original<
+  V:IntoView+'static,
+  Fut:std::future::Future<Output=V>+Send+'static
+>(e:&Element,f:impl FnOnce(crate::OriginalNode) -> Fut + 'static) -> impl FnOnce() -> AnyView {
+  let orig = crate::OriginalNode::from(e.clone());
+  let fallback = orig.deep_clone();
+  move || {
+    let fut = f(orig);
+    view!{<Suspense fallback=move || fallback>{Suspend::new(fut)}</Suspense>}.into_any()
+  }
+}
+
+/// Returns a replacement (for use in a `replace` function) that moves the original element `e` itself
+/// into the view returned by `wrapper`, rather than discarding it; e.g.:
+/// ```ignore
+/// let replace = |e:&Element| e.has_attribute("data-popup").then(||
+///   wrap_original(e, |orig| view!(<Popup><DomCont orig skip_head=true cont=replace_inner/></Popup>))
+/// );
+/// ```
+/// Since the very same DOM node is reinserted, event listeners attached to it (e.g. by other scripts on the
+/// page) keep working. If it ends up at the top level of the replacement, it keeps the `data-leptos-hydrated`
+/// attribute this crate uses to recognize replacements (which e.g. shows up in its `outer_html`).
+pub fn wrap_original<V:IntoView>(e:&Element,wrapper:impl FnOnce(crate::OriginalNode) -> V) -> impl FnOnce() -> V {
+  let orig = crate::OriginalNode::from(e.clone());
+  move || wrapper(orig)
+}
+
+/// Wraps the view `v` such that, once mounted, all attributes of `orig` that don't start with
+/// `data-` are copied onto the root element of `v`. See [`inherit_attributes`].
+pub fn with_inherited_attrs(orig:&Element,v:impl IntoView + 'static) -> impl IntoView {
+  with_inherited_attrs_filtered(orig, |a| !a.starts_with("data-"), v)
+}
+
+/// Like [`with_inherited_attrs`], but only copies the attributes for which `filter` returns `true`.
+pub fn with_inherited_attrs_filtered(
+  orig:&Element,
+  filter:impl Fn(&str) -> bool + Send + 'static,
+  v:impl IntoView + 'static
+) -> impl IntoView {
+  let orig = send_wrapper::SendWrapper::new(orig.clone());
+  OnMount::new(v, move |n:&Node| {
+    if let Some(e) = n.dyn_ref::<Element>() {
+      inherit_attributes(&orig, e, filter);
+    }
+  })
+}
+
+/// Copies the attributes of `from` for which `filter` returns `true` onto `to`.
+///
+/// `class` and `style` are merged; for any other attribute, values already present on `to` take
+/// precedence.
+pub fn inherit_attributes(from:&Element,to:&Element,filter:impl Fn(&str) -> bool) {
+  for name in from.get_attribute_names().iter() {
+    let Some(name) = name.as_string() else { continue };
+    if !filter(&name) { continue }
+    let Some(value) = from.get_attribute(&name) else { continue };
+    let value = match (name.as_str(),to.get_attribute(&name)) {
+      (_,None) => value,
+      ("class",Some(current)) => {
+        let mut classes : Vec<&str> = current.split_whitespace().collect();
+        for c in value.split_whitespace() {
+          if !classes.contains(&c) { classes.push(c) }
+        }
+        classes.join(" ")
+      }
+      // declarations of `to` come last, and hence take precedence
+      ("style",Some(current)) => format!("{};{current}",value.trim_end().trim_end_matches(';')),
+      _ => continue
+    };
+    let _ = to.set_attribute(&name, &value);
+  }
+}
+
+/// An element that belongs into the `<head>`, as recognized by [`replace_head_elements`].
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum HeadElement {
+  /// A `<title>`, with its text.
+  Title(String),
+  /// A `<meta>`, with its attributes.
+  Meta(Vec<(String,String)>),
+  /// A `<link>`, with its attributes.
+  Link(Vec<(String,String)>)
+}
+
+impl HeadElement {
+  /// The [`HeadElement`] corresponding to `e`, if it is a `<title>`, `<meta>` or `<link>`.
+  pub fn from_element(e:&Element) -> Option<Self> {
+    let attrs = || e.get_attribute_names().iter().filter_map(|name| {
+      let name = name.as_string()?;
+      e.get_attribute(&name).map(|v| (name,v))
+    }).collect();
+    match e.tag_name().to_ascii_lowercase().as_str() {
+      "title" => Some(Self::Title(e.text_content().unwrap_or_default())),
+      "meta" => Some(Self::Meta(attrs())),
+      "link" => Some(Self::Link(attrs())),
+      _ => None
+    }
+  }
+}
+
+/// Returns a `replace` function that replaces `<title>`, `<meta>` and `<link>` elements found in hydrated
+/// content by the views returned by `f`, rather than treating them as generic content; e.g.:
+/// ```ignore
+/// let head = replace_head_elements(|h| match h {
+///   HeadElement::Title(text) => view!(<MyTitle text/>).into_any(),
+///   _ => ().into_any()
+/// });
+/// ```
+/// Combine it with other rules via e.g. [`HydrationRules`](crate::HydrationRules), or call it first in a
+/// custom `replace` function. See `hydrate_head` (with the `leptos_meta` feature) for handing them over
+/// to `leptos_meta`.
+pub fn replace_head_elements<V:IntoView+'static>(
+  f:impl Fn(HeadElement) -> V + Clone + Send + 'static
+) -> impl Fn(&Element) -> Option<Box<dyn FnOnce() -> V>> + Clone + Send + 'static {
+  move |e:&Element| {
+    let h = HeadElement::from_element(e)?;
+    let f = f.clone();
+    Some(Box::new(move || f(h)) as Box<dyn FnOnce() -> V>)
+  }
+}
+
+/// Hands `<title>`, `<meta>` and `<link>` elements found in hydrated content over to `leptos_meta` (requires
+/// the `leptos_meta` feature): For use in (or as) a `replace` function, this replaces them by the corresponding
+/// [`Title`](leptos_meta::Title), [`Meta`](leptos_meta::Meta) and [`Link`](leptos_meta::Link) components,
+/// which manage them in the `<head>` reactively (deduplicated, and removed again when the replacement is
+/// cleaned up); e.g.:
+/// ```ignore
+/// provide_meta_context();
+/// let replace = |e:&Element| hydrate_head(e).or_else(|| /* other rules */);
+/// ```
+/// Attributes the respective component does not support (e.g. `data-*` attributes) are dropped.
+#[cfg(feature="leptos_meta")]
+pub fn hydrate_head(e:&Element) -> Option<impl FnOnce() -> AnyView> {
+  replace_head_elements(head_view)(e)
+}
+
+#[cfg(feature="leptos_meta")]
+fn head_view(h:HeadElement) -> AnyView {
+  fn get(attrs:&[(String,String)],name:&str) -> Option<String> {
+    attrs.iter().find(|(k,_)| k.eq_ignore_ascii_case(name)).map(|(_,v)| v.clone())
+  }
+  match h {
+    HeadElement::Title(text) => leptos_meta::Title(leptos_meta::TitleProps {
+      formatter:None,text:Some(text.into())
+    }).into_any(),
+    HeadElement::Meta(attrs) => {
+      let a = |name| get(&attrs,name).map(Into::into);
+      leptos_meta::Meta(leptos_meta::MetaProps {
+        charset:a("charset"),name:a("name"),property:a("property"),
+        http_equiv:a("http-equiv"),itemprop:a("itemprop"),content:a("content")
+      }).into_any()
+    }
+    HeadElement::Link(attrs) => {
+      let a = |name| get(&attrs,name).map(Into::into);
+      leptos_meta::Link(leptos_meta::LinkProps {
+        id:a("id"),as_:a("as"),crossorigin:a("crossorigin"),fetchpriority:a("fetchpriority"),
+        href:a("href"),hreflang:a("hreflang"),imagesizes:a("imagesizes"),imagesrcset:a("imagesrcset"),
+        integrity:a("integrity"),media:a("media"),referrerpolicy:a("referrerpolicy"),rel:a("rel"),
+        sizes:a("sizes"),title:a("title"),type_:a("type"),blocking:a("blocking")
+      }).into_any()
+    }
+  }
+}
+
+/// Collects all `data-*` attributes of `e` into a map from their names (without the `data-` prefix, e.g.
+/// `popup-delay` for `data-popup-delay`) to their values; e.g. as a uniform config bag for replacement components.
+pub fn dataset_to_map(e:&Element) -> std::collections::HashMap<String,String> {
+  e.get_attribute_names().iter().filter_map(|name| {
+    let name = name.as_string()?;
+    let key = name.strip_prefix("data-")?.to_string();
+    e.get_attribute(&name).map(|v| (key,v))
+  }).collect()
+}
+
+/// Deserializes the `data-*` attributes of `e` (as collected by [`dataset_to_map`]) into a typed config
+/// (requires the `serde` feature):
+/// ```ignore
+/// #[derive(serde::Deserialize)]
+/// #[serde(rename_all = "kebab-case")]
+/// struct PopupConfig { popup_title:String, popup_position:Option<String> }
+/// let config : PopupConfig = parse_dataset(e)?;
+/// ```
+/// All values are strings; fields of other types need to be deserialized from strings (e.g. via
+/// `#[serde(deserialize_with=..)]`).
+#[cfg(feature="serde")]
+pub fn parse_dataset<T:serde::de::DeserializeOwned>(e:&Element) -> Result<T,serde::de::value::Error> {
+  T::deserialize(serde::de::value::MapDeserializer::new(dataset_to_map(e).into_iter()))
+}
@@ -0,0 +1,77 @@
+//! Browser tests for the removal of hydrated nodes on cleanup; run via
+//! `wasm-pack test --headless --firefox --features csr,test-util`.
+#![cfg(all(target_arch="wasm32",feature="csr",feature="test-util"))]
+
+use leptos::prelude::*;
+use leptos::web_sys::{Element,Node};
+use leptos_dyn_dom::hydrate_node;
+use leptos_dyn_dom::test_util::fixture;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+// Hydrates `e` (without replacing anything), registering its removal with the current owner.
+fn register(e:&Element) {
+  hydrate_node(e.clone().into(), &|_:&Element| None::<fn()>);
+}
+
+// `<div><div id="outer"><div id="inner"></div></div></div>`
+fn nested() -> (Element,Element,Element) {
+  let root = fixture(r#"<div id="outer"><div id="inner"></div></div>"#);
+  let outer = root.first_element_child().unwrap();
+  let inner = outer.first_element_child().unwrap();
+  (root,outer,inner)
+}
+
+#[wasm_bindgen_test]
+fn removes_nested_nodes() {
+  let (root,outer,inner) = nested();
+  let owner = Owner::new();
+  owner.with(|| {
+    register(&outer);
+    Owner::new().with(|| register(&inner));
+  });
+  owner.cleanup();
+  assert!(outer.parent_node().is_none());
+  assert!(inner.parent_node().is_none());
+  assert!(root.first_child().is_none());
+}
+
+#[wasm_bindgen_test]
+fn tolerates_removed_parent() {
+  let (root,outer,inner) = nested();
+  let owner = Owner::new();
+  owner.with(|| {
+    register(&inner);
+    register(&outer);
+  });
+  // the parent is gone before the child's cleanup runs
+  root.remove_child(&outer).unwrap();
+  owner.cleanup();
+  assert!(outer.parent_node().is_none());
+  assert!(inner.parent_node().is_none());
+}
+
+#[wasm_bindgen_test]
+fn leaves_moved_nodes_alone() {
+  let (_root,outer,inner) = nested();
+  let other = fixture("");
+  let owner = Owner::new();
+  owner.with(|| register(&inner));
+  other.append_child(&inner).unwrap();
+  owner.cleanup();
+  assert_eq!(inner.parent_node(), Some(Node::from(other)));
+  assert!(outer.first_child().is_none());
+}
+
+#[wasm_bindgen_test]
+fn removes_nodes_mounted_after_registering() {
+  let detached = fixture("<p>a</p>");
+  let parent = fixture("");
+  let owner = Owner::new();
+  owner.with(|| register(&detached));
+  parent.append_child(&detached).unwrap();
+  owner.cleanup();
+  assert!(detached.parent_node().is_none());
+  assert!(parent.first_child().is_none());
+}
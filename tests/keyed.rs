@@ -0,0 +1,78 @@
+//! Browser tests for `DomStringContReactive`; run via `wasm-pack test --headless --firefox --features csr,test-util`.
+#![cfg(all(target_arch="wasm32",feature="csr",feature="test-util"))]
+
+use leptos::prelude::*;
+use leptos::wasm_bindgen::JsCast;
+use leptos::web_sys::{Element,js_sys::Promise};
+use leptos_dyn_dom::DomStringContReactive;
+use leptos_dyn_dom::test_util::fixture;
+use std::{cell::Cell,rc::Rc};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+// Waits (one task at a time) until `done` returns `true`.
+async fn until(done:impl Fn() -> bool) {
+  for _ in 0..100 {
+    if done() { return }
+    let tick = Promise::new(&mut |resolve,_| { let _ = window().set_timeout_with_callback(&resolve); });
+    wasm_bindgen_futures::JsFuture::from(tick).await.unwrap();
+  }
+  panic!("timed out");
+}
+
+#[wasm_bindgen_test]
+async fn hydrates_rendered_markup_in_place() {
+  let parent = fixture("");
+  let on_load = RwSignal::new(false);
+  let html = Signal::stored(r#"<p>a</p>"#.to_string());
+  let cont = |_:&Element| None::<fn()>;
+  let handle = leptos::mount::mount_to(parent.clone().unchecked_into(), move || view!{
+    <DomStringContReactive html cont on_load keyed=true/>
+  });
+  let p = parent.query_selector("p").unwrap().expect("no <p>");
+  until(|| on_load.get_untracked()).await;
+  assert!(p.is_connected(), "the rendered markup has been replaced");
+  handle.forget();
+}
+
+#[wasm_bindgen_test]
+async fn keeps_keyed_replacements() {
+  let parent = fixture("");
+  let on_load = RwSignal::new(false);
+  let html = RwSignal::new(r#"<p data-key="a">a</p>"#.to_string());
+  let builds = Rc::new(Cell::new(0));
+  let cont = {
+    let builds = builds.clone();
+    move |e:&Element| e.has_attribute("data-key").then(|| {
+      builds.set(builds.get() + 1);
+      || view!(<b>"x"</b>)
+    })
+  };
+  let handle = leptos::mount::mount_to(parent.clone().unchecked_into(), move || view!{
+    <DomStringContReactive html cont on_load keyed=true/>
+  });
+  until(|| on_load.get_untracked()).await;
+  assert_eq!(builds.get(), 1);
+  on_load.set(false);
+  html.set(r#"<p data-key="a">a</p><p data-key="b">b</p>"#.to_string());
+  until(|| on_load.get_untracked()).await;
+  assert_eq!(builds.get(), 2);
+  assert_eq!(parent.query_selector_all("b").unwrap().length(), 2);
+  handle.forget();
+}
+
+#[wasm_bindgen_test]
+async fn hydrates_duplicate_keys_without_key() {
+  let parent = fixture("");
+  let on_load = RwSignal::new(false);
+  let html = Signal::stored(r#"<p data-key="a">1</p><p data-key="a">2</p>"#.to_string());
+  let cont = |e:&Element| e.has_attribute("data-key").then_some(|| view!(<b>"x"</b>));
+  let handle = leptos::mount::mount_to(parent.clone().unchecked_into(), move || view!{
+    <DomStringContReactive html cont on_load keyed=true/>
+  });
+  until(|| on_load.get_untracked()).await;
+  assert_eq!(parent.query_selector_all("b").unwrap().length(), 2);
+  assert!(parent.query_selector("p").unwrap().is_none());
+  handle.forget();
+}
@@ -0,0 +1,43 @@
+//! Browser tests for MathML content; run via `wasm-pack test --headless --firefox --features csr,test-util`.
+#![cfg(all(target_arch="wasm32",feature="csr",feature="test-util"))]
+
+use leptos::prelude::*;
+use leptos::wasm_bindgen::JsCast;
+use leptos::web_sys::{Element,js_sys::Promise};
+use leptos_dyn_dom::DomStringContMath;
+use leptos_dyn_dom::test_util::fixture;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+const MATHML_NS: &str = "http://www.w3.org/1998/Math/MathML";
+
+// Waits (one task at a time) until `done` returns `true`.
+async fn until(done:impl Fn() -> bool) {
+  for _ in 0..100 {
+    if done() { return }
+    let tick = Promise::new(&mut |resolve,_| { let _ = window().set_timeout_with_callback(&resolve); });
+    wasm_bindgen_futures::JsFuture::from(tick).await.unwrap();
+  }
+  panic!("timed out");
+}
+
+#[wasm_bindgen_test]
+async fn injects_mathml_in_mathml_namespace() {
+  let parent = fixture("");
+  let on_load = RwSignal::new(false);
+  let html = "<mi>x</mi><mn>2</mn>".to_string();
+  let cont = |_:&Element| None::<fn()>;
+  let handle = leptos::mount::mount_to(parent.clone().unchecked_into(), move || view!{
+    <math><DomStringContMath html cont on_load/></math>
+  });
+  until(|| on_load.get_untracked()).await;
+  let mrow = parent.query_selector("mrow").unwrap().expect("no <mrow>");
+  assert_eq!(mrow.child_element_count(), 2);
+  let mut child = mrow.first_element_child();
+  while let Some(c) = child {
+    assert_eq!(c.namespace_uri().as_deref(), Some(MATHML_NS), "<{}> is not a MathML element", c.local_name());
+    child = c.next_element_sibling();
+  }
+  handle.forget();
+}
@@ -0,0 +1,45 @@
+//! Tests rendering `DomChildren` server-side and hydrating the result: The server part runs natively (without
+//! the `csr`/`hydrate` features); the client part in a browser, via
+//! `wasm-pack test --headless --firefox --features csr,test-util`.
+#![cfg(any(not(any(feature="csr",feature="hydrate")),all(target_arch="wasm32",feature="csr",feature="test-util")))]
+
+// what the server renders for `DomChildren` with `OriginalNode::from_html(CHILDREN)`
+const CHILDREN: &str = "<b>x</b> y";
+const SERVER_HTML: &str = "<!--leptos-dyn-dom--><b>x</b> y<!--/leptos-dyn-dom-->";
+
+#[cfg(not(any(feature="csr",feature="hydrate")))]
+#[test]
+fn server_encloses_markup_in_markers() {
+  use leptos::prelude::*;
+  use leptos_dyn_dom::{DomChildren,OriginalNode};
+  let orig = OriginalNode::from_html(CHILDREN);
+  assert_eq!(view!(<DomChildren orig/>).to_html(), SERVER_HTML);
+}
+
+#[cfg(all(target_arch="wasm32",feature="csr",feature="test-util"))]
+mod client {
+  use leptos::prelude::*;
+  use leptos::tachys::{hydration::Cursor,view::{Position,PositionState}};
+  use leptos_dyn_dom::{DomChildren,OriginalNode};
+  use leptos_dyn_dom::test_util::fixture;
+  use wasm_bindgen_test::wasm_bindgen_test;
+
+  wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+  #[wasm_bindgen_test]
+  fn hydration_replaces_server_markup() {
+    let root = fixture(&format!("{}<i>after</i>",super::SERVER_HTML));
+    let server_b = root.query_selector("b").unwrap().expect("no server-rendered <b>");
+    let orig = OriginalNode::from_html(super::CHILDREN);
+    let client_b = orig.first_child().expect("no client-side <b>");
+    let cursor = Cursor::new(root.clone());
+    let position = PositionState::new(Position::FirstChild);
+    let state = view!(<DomChildren orig/>).hydrate::<true>(&cursor, &position);
+    assert!(server_b.parent_node().is_none(), "the server-rendered markup is still mounted");
+    assert!(root.contains(Some(&client_b)), "the client-side children have not been mounted");
+    assert_eq!(root.inner_html(), format!("{}<i>after</i>",super::SERVER_HTML));
+    // the next view continues after the end marker
+    assert_eq!(cursor.current().node_value().as_deref(), Some("/leptos-dyn-dom"));
+    std::mem::forget(state);
+  }
+}
@@ -0,0 +1,43 @@
+//! Browser tests for the `test-util` helpers; run via `wasm-pack test --headless --firefox --features csr,test-util`.
+#![cfg(all(target_arch="wasm32",feature="csr",feature="test-util"))]
+
+use leptos::prelude::*;
+use leptos::web_sys::{Element,Node};
+use leptos_dyn_dom::{hydrate_node,serialize_node};
+use leptos_dyn_dom::test_util::{fixture,hydrated_html,assert_hydrated};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+// `<div><p>a</p><p data-x>b</p></div>`, with the second `<p>` replaced by `<b>x</b>`
+fn hydrated() -> Node {
+  let root = fixture(r#"<p>a</p><p data-x>b</p>"#);
+  let owner = Owner::new();
+  owner.with(|| hydrate_node(root.clone().into(), &|e:&Element| e.has_attribute("data-x").then_some(|| view!(<b>"x"</b>))));
+  // keep the replacement mounted for the assertions
+  std::mem::forget(owner);
+  root.into()
+}
+
+#[wasm_bindgen_test]
+fn fixture_parses_html() {
+  assert_eq!(serialize_node(&fixture(r#"<p class="a">b</p>"#).into()), r#"<div><p class="a">b</p></div>"#);
+}
+
+#[wasm_bindgen_test]
+fn hydrated_html_ignores_markers() {
+  let root = hydrated();
+  assert!(serialize_node(&root).contains("data-leptos-hydrated"));
+  assert_eq!(hydrated_html(&root), "<div><p>a</p><b>x</b></div>");
+}
+
+#[wasm_bindgen_test]
+fn asserts_hydrated_markup() {
+  assert_hydrated(&hydrated(), "<div><p>a</p><b>x</b></div>");
+}
+
+#[wasm_bindgen_test]
+#[should_panic(expected = "hydrated DOM does not match the expected markup")]
+fn rejects_unexpected_markup() {
+  assert_hydrated(&hydrated(), r#"<div><p>a</p><p data-x="">b</p></div>"#);
+}
@@ -0,0 +1,106 @@
+//! Browser tests for the DFS traversal; run via `wasm-pack test --headless --firefox --features csr,test-util`.
+#![cfg(all(target_arch="wasm32",feature="csr",feature="test-util"))]
+
+use leptos::prelude::*;
+use leptos::web_sys::Element;
+use leptos::wasm_bindgen::JsCast;
+use leptos_dyn_dom::{hydrate_node_mut,hydrate_node_with,DomChildrenContPath,HydrateOpts,OriginalNode};
+use leptos_dyn_dom::test_util::{fixture,assert_hydrated};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+// Hydrates `html`, replacing elements with `data-x` by `<b>x</b>`; returns the root and the ids of the
+// elements passed to `replace`, in order.
+fn visit(html:&str) -> (Element,Vec<String>) {
+  let root = fixture(html);
+  let mut visited = Vec::new();
+  let owner = Owner::new();
+  let count = owner.with(|| hydrate_node_mut(root.clone().into(), &mut |e:&Element| {
+    visited.push(e.id());
+    e.has_attribute("data-x").then_some(|| view!(<b>"x"</b>))
+  }));
+  assert_eq!(count, 1);
+  // keep the replacements mounted for the assertions
+  std::mem::forget(owner);
+  visited.retain(|id| !id.is_empty());
+  (root,visited)
+}
+
+#[wasm_bindgen_test]
+fn replaces_only_child() {
+  let (root,visited) = visit(r#"<div id="p"><span id="a" data-x></span></div><i id="after"></i>"#);
+  assert_eq!(visited, ["p","a","after"]);
+  assert_hydrated(&root, r#"<div><div id="p"><b>x</b></div><i id="after"></i></div>"#);
+}
+
+#[wasm_bindgen_test]
+fn replaces_first_of_several() {
+  let (root,visited) = visit(r#"<span id="a" data-x></span><span id="b"></span><span id="c"></span>"#);
+  assert_eq!(visited, ["a","b","c"]);
+  assert_hydrated(&root, r#"<div><b>x</b><span id="b"></span><span id="c"></span></div>"#);
+}
+
+#[wasm_bindgen_test]
+fn replaces_last_of_several() {
+  let (root,visited) = visit(r#"<div id="p"><span id="a"></span><span id="b" data-x></span></div><span id="after"></span>"#);
+  assert_eq!(visited, ["p","a","b","after"]);
+  assert_hydrated(&root, r#"<div><div id="p"><span id="a"></span><b>x</b></div><span id="after"></span></div>"#);
+}
+
+#[wasm_bindgen_test]
+fn replaces_element_between_whitespace() {
+  let (root,visited) = visit("<div id=\"p\">\n  <span id=\"a\" data-x></span>\n  <span id=\"b\"></span>\n</div>");
+  assert_eq!(visited, ["p","a","b"]);
+  assert_hydrated(&root, "<div><div id=\"p\">\n  <b>x</b>\n  <span id=\"b\"></span>\n</div></div>");
+}
+
+#[wasm_bindgen_test]
+fn advances_indices_past_replacements() {
+  let root = fixture("<div id=\"p\">\n  <span id=\"a\" data-x></span>\n  <span id=\"b\"></span>\n</div><span id=\"c\"></span>");
+  let orig = OriginalNode::from(root.clone());
+  let visited = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+  let cont = {
+    let visited = visited.clone();
+    move |e:&Element,path:&[u32]| {
+      visited.lock().unwrap().push((e.id(),path.to_vec()));
+      e.has_attribute("data-x").then_some(|| view!(<b>"x"</b>))
+    }
+  };
+  let parent : leptos::web_sys::HtmlElement = fixture("").unchecked_into();
+  let handle = leptos::mount::mount_to(parent, move || view!(<DomChildrenContPath orig cont/>));
+  // the text nodes count as well: `a` is the second and `b` the fourth child of `p`
+  assert_eq!(*visited.lock().unwrap(), [
+    ("p".to_string(),vec![0]),
+    ("a".to_string(),vec![0,1]),
+    ("b".to_string(),vec![0,3]),
+    ("c".to_string(),vec![1])
+  ]);
+  handle.forget();
+}
+
+// Hydrates the `<section>` in `<div><section data-x><p data-x>a</p></section></div>`; returns the `<div>`.
+fn hydrate_section(include_root:bool) -> Element {
+  let container = fixture(r#"<section data-x><p data-x>a</p></section>"#);
+  let root = container.first_element_child().unwrap();
+  let owner = Owner::new();
+  let count = owner.with(|| hydrate_node_with(
+    root.into(),
+    &|e:&Element| e.has_attribute("data-x").then_some(|| view!(<b>"x"</b>)),
+    &HydrateOpts::new().include_root(include_root)
+  ));
+  assert_eq!(count, 1);
+  // keep the replacements mounted for the assertions
+  std::mem::forget(owner);
+  container
+}
+
+#[wasm_bindgen_test]
+fn replaces_root_if_included() {
+  assert_hydrated(&hydrate_section(true), "<div><b>x</b></div>");
+}
+
+#[wasm_bindgen_test]
+fn replaces_only_descendants_if_root_excluded() {
+  assert_hydrated(&hydrate_section(false), r#"<div><section data-x=""><b>x</b></section></div>"#);
+}